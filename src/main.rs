@@ -11,6 +11,434 @@ mod day {
     pub trait Solution {
         fn part_1(&self) -> Box<dyn Display>;
         fn part_2(&self) -> Box<dyn Display>;
+
+        // extra detail on how the answers came about, printed when run with --report
+        fn report(&self) -> Option<String> {
+            None
+        }
+    }
+}
+
+mod math {
+    use num::{BigInt, Integer, Signed};
+
+    pub fn gcd_all<T: Integer + Clone>(xs: impl IntoIterator<Item=T>) -> T {
+        xs.into_iter().fold(T::zero(), |acc, x| acc.gcd(&x))
+    }
+
+    pub fn lcm_all<T: Integer + Clone>(xs: impl IntoIterator<Item=T>) -> T {
+        xs.into_iter().fold(T::one(), |acc, x| acc.lcm(&x))
+    }
+
+    // combines congruences x = offset (mod modulus) into a single (offset, modulus), with the
+    // offset in 0..modulus. moduli don't need to be coprime; None if the congruences conflict
+    // or a modulus isn't positive
+    pub fn crt(congruences: &[(i128, i128)]) -> Option<(i128, i128)> {
+        congruences.iter().try_fold((0i128, 1i128), |(a1, m1), &(a2, m2)| {
+            if m2 <= 0 {
+                return None;
+            }
+            let egcd = m1.extended_gcd(&m2);
+            let diff = a2 - a1;
+            if diff % egcd.gcd != 0 {
+                return None;
+            }
+            // a1 + m1 * k = a2 (mod m2)  =>  k = diff / gcd * x (mod m2 / gcd). both factors are
+            // below m2 / gcd, but their product needn't fit in an i128
+            let reduced = m2 / egcd.gcd;
+            let k = BigInt::from((diff / egcd.gcd).rem_euclid(reduced)) * egcd.x.rem_euclid(reduced) % reduced;
+            let k = i128::try_from(k).unwrap();
+            let modulus = m1.checked_mul(reduced).expect("combined modulus overflows i128");
+            // a1 < m1 and k < reduced, so this stays below the modulus
+            Some((a1 + m1 * k, modulus))
+        })
+    }
+
+    // floor of the square root
+    pub fn isqrt(n: &BigInt) -> BigInt {
+        assert!(!n.is_negative(), "square root of negative number: {}", n);
+        n.sqrt()
+    }
+
+    pub fn exact_sqrt(n: &BigInt) -> Option<BigInt> {
+        if n.is_negative() {
+            return None;
+        }
+        let root = n.sqrt();
+        if &root * &root == *n { Some(root) } else { None }
+    }
+
+    // the inclusive range of integers x for which a*x^2 + b*x + c < 0, for a > 0
+    pub fn quadratic_negative_range(a: &BigInt, b: &BigInt, c: &BigInt) -> Option<(BigInt, BigInt)> {
+        assert!(a.is_positive(), "quadratic must open upwards");
+        let f = |x: &BigInt| (a * x + b) * x + c;
+
+        let discriminant = b * b - BigInt::from(4) * a * c;
+        if !discriminant.is_positive() {
+            return None;
+        }
+        let root = isqrt(&discriminant);
+        let two_a: BigInt = a * 2;
+
+        // the floored roots are within one of the real roots, so only a few steps of
+        // adjustment are needed to land on the exact integer bounds
+        let mut lo = (-b - &root).div_floor(&two_a);
+        while f(&lo).is_negative() {
+            lo -= 1;
+        }
+        lo += 1;
+        if !f(&lo).is_negative() {
+            return None;
+        }
+
+        let mut hi = (-b + &root).div_floor(&two_a).max(lo.clone());
+        while f(&(&hi + 1)).is_negative() {
+            hi += 1;
+        }
+        while !f(&hi).is_negative() {
+            hi -= 1;
+        }
+
+        Some((lo, hi))
+    }
+}
+
+mod memo {
+    use std::collections::HashMap;
+    use std::fmt::{Display, Formatter};
+    use std::hash::Hash;
+
+    #[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+    pub struct MemoStats {
+        pub hits: u64,
+        pub misses: u64,
+    }
+
+    impl Display for MemoStats {
+        fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+            write!(f, "{} hits, {} misses", self.hits, self.misses)
+        }
+    }
+
+    // cache for recursive solutions. keys can borrow from the puzzle input (e.g. a suffix
+    // slice), so building them doesn't need to allocate
+    pub struct Memo<K, V> {
+        cache: HashMap<K, V>,
+        stats: Option<MemoStats>,
+    }
+
+    impl<K: Hash + Eq, V: Clone> Memo<K, V> {
+        pub fn new() -> Self {
+            Memo {
+                cache: HashMap::new(),
+                stats: None,
+            }
+        }
+
+        pub fn with_stats() -> Self {
+            Memo {
+                stats: Some(MemoStats::default()),
+                ..Memo::new()
+            }
+        }
+
+        pub fn stats(&self) -> Option<MemoStats> {
+            self.stats
+        }
+
+        // returns the cached value for the key, or computes it with `f`. `f` is given the
+        // memo back so that it can recurse
+        pub fn get_or_insert_with<F>(&mut self, key: K, f: F) -> V
+            where F: FnOnce(&mut Self) -> V
+        {
+            if let Some(cached) = self.cache.get(&key) {
+                if let Some(stats) = self.stats.as_mut() {
+                    stats.hits += 1;
+                }
+                return cached.clone();
+            }
+            if let Some(stats) = self.stats.as_mut() {
+                stats.misses += 1;
+            }
+
+            let v = f(self);
+            self.cache.insert(key, v.clone());
+            v
+        }
+    }
+}
+
+mod parse {
+    use std::fmt::{Debug, Display, Formatter};
+    use std::str::FromStr;
+    use num::Num;
+
+    pub struct ParseError {
+        pub line: usize,
+        pub column: usize,
+        pub message: String,
+    }
+
+    impl Display for ParseError {
+        fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+            write!(f, "line {}, column {}: {}", self.line, self.column, self.message)
+        }
+    }
+
+    impl Debug for ParseError {
+        fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+            Display::fmt(self, f)
+        }
+    }
+
+    pub type ParseResult<T> = Result<T, ParseError>;
+
+    // parses the whole input, panicking with the location of the first error
+    pub fn parse<'a, T, F>(input: &'a str, f: F) -> T
+        where F: FnOnce(&mut Parser<'a>) -> ParseResult<T>
+    {
+        let mut parser = Parser::new(input);
+        f(&mut parser)
+            .and_then(|v| {
+                parser.take_while(|c| c == '\n');
+                parser.end()?;
+                Ok(v)
+            })
+            .unwrap_or_else(|e| panic!("invalid input: {}", e))
+    }
+
+    // a cursor over part of the input. sub-parsers for lines and sections keep the whole
+    // source around so that errors can report where they happened in the original text
+    #[derive(Clone)]
+    pub struct Parser<'a> {
+        source: &'a str,
+        pos: usize,
+        end: usize,
+    }
+
+    impl<'a> Parser<'a> {
+        pub fn new(input: &'a str) -> Self {
+            Parser {
+                source: input,
+                pos: 0,
+                end: input.len(),
+            }
+        }
+
+        pub fn rest(&self) -> &'a str {
+            &self.source[self.pos..self.end]
+        }
+
+        pub fn peek(&self) -> Option<char> {
+            self.rest().chars().next()
+        }
+
+        pub fn is_empty(&self) -> bool {
+            self.pos == self.end
+        }
+
+        fn error_at<T>(&self, pos: usize, message: String) -> ParseResult<T> {
+            let before = &self.source[..pos];
+            let line = before.matches('\n').count() + 1;
+            let column = before[before.rfind('\n').map_or(0, |i| i + 1)..].chars().count() + 1;
+            Err(ParseError { line, column, message })
+        }
+
+        fn found(&self) -> String {
+            match self.rest().split_whitespace().next() {
+                Some(token) if !self.rest().starts_with(char::is_whitespace) =>
+                    format!("{:?}", token.chars().take(12).collect::<String>()),
+                _ if self.is_empty() => "end of line".to_owned(),
+                _ => format!("{:?}", self.peek().unwrap()),
+            }
+        }
+
+        // an error at the current position, mentioning what was found there
+        pub fn error<T>(&self, expected: &str) -> ParseResult<T> {
+            self.error_at(self.pos, format!("expected {}, found {}", expected, self.found()))
+        }
+
+        pub fn end(&self) -> ParseResult<()> {
+            if self.is_empty() { Ok(()) } else { self.error("end of line") }
+        }
+
+        pub fn tag(&mut self, tag: &str) -> ParseResult<()> {
+            if self.try_tag(tag) { Ok(()) } else { self.error(&format!("{:?}", tag)) }
+        }
+
+        pub fn try_tag(&mut self, tag: &str) -> bool {
+            let matched = self.rest().starts_with(tag);
+            if matched {
+                self.pos += tag.len();
+            }
+            matched
+        }
+
+        pub fn take_while<P: Fn(char) -> bool>(&mut self, predicate: P) -> &'a str {
+            let rest = self.rest();
+            let len = rest.find(|c| !predicate(c)).unwrap_or(rest.len());
+            self.pos += len;
+            &rest[..len]
+        }
+
+        pub fn spaces(&mut self) {
+            self.take_while(|c| c == ' ');
+        }
+
+        // a non-empty run of letters and digits
+        pub fn word(&mut self) -> ParseResult<&'a str> {
+            if !self.peek().is_some_and(char::is_alphanumeric) {
+                return self.error("a word");
+            }
+            Ok(self.take_while(char::is_alphanumeric))
+        }
+
+        // exactly `width` characters, whatever they are
+        pub fn fixed(&mut self, width: usize) -> ParseResult<&'a str> {
+            match self.rest().char_indices().nth(width) {
+                Some((len, _)) => {
+                    let token = &self.rest()[..len];
+                    self.pos += len;
+                    Ok(token)
+                }
+                None if self.rest().chars().count() == width => Ok(self.take_while(|_| true)),
+                None => self.error(&format!("{} characters", width)),
+            }
+        }
+
+        pub fn integer<T: FromStr>(&mut self) -> ParseResult<T> {
+            let start = self.pos;
+            let sign = if self.rest().starts_with(['-', '+']) { 1 } else { 0 };
+            let digits = self.rest()[sign..].find(|c: char| !c.is_ascii_digit()).unwrap_or(self.rest().len() - sign);
+            if digits == 0 {
+                return self.error("an integer");
+            }
+            let token = &self.rest()[..sign + digits];
+            self.pos += token.len();
+            token.parse().or_else(|_| self.error_at(start, format!("integer out of range: {}", token)))
+        }
+
+        // a fixed-width integer in the given radix, e.g. a 5-digit hex number
+        pub fn radix_integer<T: Num>(&mut self, width: usize, radix: u32) -> ParseResult<T> {
+            let start = self.pos;
+            let token = self.fixed(width)?;
+            T::from_str_radix(token, radix)
+                .or_else(|_| self.error_at(start, format!("expected base-{} integer, found {:?}", radix, token)))
+        }
+
+        // converts the token taken by `take` with its FromStr implementation
+        pub fn token<T, F>(&mut self, take: F) -> ParseResult<T>
+            where T: FromStr, F: FnOnce(&mut Self) -> ParseResult<&'a str>
+        {
+            let start = self.pos;
+            let token = take(self)?;
+            token.parse().or_else(|_| {
+                let type_name = std::any::type_name::<T>().rsplit("::").next().unwrap();
+                self.error_at(start, format!("invalid {}: {:?}", type_name, token))
+            })
+        }
+
+        pub fn one_of<T: Copy>(&mut self, options: &[(&str, T)]) -> ParseResult<T> {
+            match options.iter().find(|(tag, _)| self.rest().starts_with(tag)) {
+                Some((tag, value)) => {
+                    self.pos += tag.len();
+                    Ok(*value)
+                }
+                None => {
+                    let expected: Vec<String> = options.iter().map(|(tag, _)| format!("{:?}", tag)).collect();
+                    self.error(&format!("one of {}", expected.join(", ")))
+                }
+            }
+        }
+
+        // one or more items, with `separator` between each
+        pub fn separated<T, F>(&mut self, separator: &str, mut item: F) -> ParseResult<Vec<T>>
+            where F: FnMut(&mut Self) -> ParseResult<T>
+        {
+            let mut items = vec![item(self)?];
+            while self.try_tag(separator) {
+                items.push(item(self)?);
+            }
+            Ok(items)
+        }
+
+        // items separated by any number of spaces, up to the end of the line or the first
+        // character that can't start an item
+        pub fn spaced<T, F>(&mut self, mut item: F) -> ParseResult<Vec<T>>
+            where F: FnMut(&mut Self) -> ParseResult<T>
+        {
+            let mut items = vec![];
+            self.spaces();
+            while self.peek().is_some_and(|c| c.is_alphanumeric() || c == '-' || c == '+') {
+                items.push(item(self)?);
+                self.spaces();
+            }
+            Ok(items)
+        }
+
+        // `key=value`, for a particular key
+        pub fn field<T, F>(&mut self, key: &str, separator: &str, value: F) -> ParseResult<T>
+            where F: FnOnce(&mut Self) -> ParseResult<T>
+        {
+            self.tag(key)?;
+            self.tag(separator)?;
+            value(self)
+        }
+
+        fn sub_parser(&self, start: usize, end: usize) -> Parser<'a> {
+            Parser {
+                source: self.source,
+                pos: start,
+                end,
+            }
+        }
+
+        // parses the next line with `item`, which must consume all of it
+        pub fn line<T, F>(&mut self, item: F) -> ParseResult<T>
+            where F: FnOnce(&mut Self) -> ParseResult<T>
+        {
+            let len = self.rest().find('\n').unwrap_or(self.rest().len());
+            let mut sub_parser = self.sub_parser(self.pos, self.pos + len);
+            let v = item(&mut sub_parser)?;
+            sub_parser.end()?;
+            self.pos += len;
+            self.try_tag("\n");
+            Ok(v)
+        }
+
+        pub fn lines<T, F>(&mut self, mut item: F) -> ParseResult<Vec<T>>
+            where F: FnMut(&mut Self) -> ParseResult<T>
+        {
+            let mut items = vec![];
+            while !self.is_empty() {
+                items.push(self.line(&mut item)?);
+            }
+            Ok(items)
+        }
+
+        // parses everything up to the next blank line with `item`, which must consume all of it
+        pub fn section<T, F>(&mut self, item: F) -> ParseResult<T>
+            where F: FnOnce(&mut Self) -> ParseResult<T>
+        {
+            let rest = self.rest();
+            let len = rest[..rest.find("\n\n").unwrap_or(rest.len())].trim_end_matches('\n').len();
+            let mut sub_parser = self.sub_parser(self.pos, self.pos + len);
+            let v = item(&mut sub_parser)?;
+            sub_parser.end()?;
+            self.pos += len;
+            self.take_while(|c| c == '\n');
+            Ok(v)
+        }
+
+        pub fn sections<T, F>(&mut self, mut item: F) -> ParseResult<Vec<T>>
+            where F: FnMut(&mut Self) -> ParseResult<T>
+        {
+            let mut items = vec![];
+            while !self.is_empty() {
+                items.push(self.section(&mut item)?);
+            }
+            Ok(items)
+        }
     }
 }
 
@@ -58,11 +486,17 @@ fn main() {
     let part_2_time = part_2_start.elapsed();
     println!("{}", part_2_answer);
     println!("Part 2 time: {}ms", part_2_time.as_millis());
+
+    if std::env::args().any(|arg| arg == "--report") {
+        if let Some(report) = solution.report() {
+            print!("{}", report);
+        }
+    }
 }
 
 mod day_01 {
-    use std::fmt::Display;
-    use regex::Regex;
+    use std::fmt::{Display, Formatter, Write};
+    use std::ops::Range;
     use crate::day::{Day, Solution};
 
     pub struct Day01;
@@ -82,73 +516,199 @@ mod day_01 {
         }
     }
 
+    // the tokens that count as digits, and their values
+    struct Vocabulary {
+        tokens: Vec<(String, i32)>,
+    }
+
+    impl Vocabulary {
+        fn digits() -> Self {
+            Vocabulary {
+                tokens: vec![],
+            }.with_words(&["0", "1", "2", "3", "4", "5", "6", "7", "8", "9"], 0)
+        }
+
+        fn english() -> Self {
+            Self::digits()
+                .with_words(&["one", "two", "three", "four", "five", "six", "seven", "eight", "nine"], 1)
+        }
+
+        // adds consecutively-valued words, e.g. the words for 1 to 9 in some language
+        fn with_words(self, words: &[&str], first_value: i32) -> Self {
+            words.iter()
+                .zip(first_value..)
+                .fold(self, |vocabulary, (word, value)| vocabulary.with_token(word, value))
+        }
+
+        // a single extra token, e.g. "ten" or "zero"
+        fn with_token(mut self, token: &str, value: i32) -> Self {
+            self.tokens.push((token.to_owned(), value));
+            self
+        }
+
+        // every token in the line, by position. tokens can overlap, so "twone" has both a
+        // two and a one. if several tokens start at the same place, the longest wins
+        fn matches<'a>(&'a self, line: &'a str) -> impl Iterator<Item=DigitMatch> + 'a {
+            line.char_indices()
+                .filter_map(|(i, _)| {
+                    self.tokens.iter()
+                        .filter(|(token, _)| line[i..].starts_with(token.as_str()))
+                        .max_by_key(|(token, _)| token.len())
+                        .map(|(token, value)| DigitMatch { span: i..i + token.len(), value: *value })
+                })
+        }
+
+        fn calibrate(&self, line_number: usize, line: &str) -> Calibration {
+            let mut matches = self.matches(line);
+            let first = matches.next();
+            let last = matches.last().or_else(|| first.clone());
+            Calibration {
+                line_number,
+                first,
+                last,
+            }
+        }
+    }
+
+    #[derive(Debug, Clone)]
+    struct DigitMatch {
+        span: Range<usize>,
+        value: i32,
+    }
+
+    // how one line decoded. lines without any digits have neither a first nor a last
+    #[derive(Debug)]
+    struct Calibration {
+        line_number: usize,
+        first: Option<DigitMatch>,
+        last: Option<DigitMatch>,
+    }
+
+    impl Calibration {
+        fn is_valid(&self) -> bool {
+            self.first.is_some()
+        }
+
+        fn value(&self) -> i32 {
+            match (&self.first, &self.last) {
+                (Some(first), Some(last)) => first.value * 10 + last.value,
+                _ => 0,
+            }
+        }
+
+        // the part of the line from the first digit to the end of the last
+        fn span(&self) -> Option<Range<usize>> {
+            Some(self.first.as_ref()?.span.start..self.last.as_ref()?.span.end)
+        }
+    }
+
+    impl Display for Calibration {
+        fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+            match (&self.first, &self.last, self.span()) {
+                (Some(first), Some(last), Some(span)) => write!(f, "line {}: {} at {:?}, {} at {:?} => {} (from {:?})",
+                    self.line_number, first.value, first.span, last.value, last.span, self.value(), span),
+                _ => write!(f, "line {}: no digits", self.line_number),
+            }
+        }
+    }
+
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    enum Leniency {
+        // leave lines without digits out of the calibrations
+        SkipInvalid,
+        // keep lines without digits, counting them as zero
+        ZeroInvalid,
+    }
+
+    struct Audit {
+        calibrations: Vec<Calibration>,
+        // line numbers without any digits
+        invalid: Vec<usize>,
+    }
+
+    impl Audit {
+        fn total(&self) -> i32 {
+            self.calibrations.iter().map(Calibration::value).sum()
+        }
+    }
+
+    impl Display for Audit {
+        fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+            for calibration in &self.calibrations {
+                writeln!(f, "{}", calibration)?;
+            }
+            writeln!(f, "total {}", self.total())?;
+            if !self.invalid.is_empty() {
+                let lines: Vec<String> = self.invalid.iter().map(|line| line.to_string()).collect();
+                writeln!(f, "no digits on lines {}", lines.join(", "))?;
+            }
+            Ok(())
+        }
+    }
+
+    impl Input {
+        fn audit(&self, vocabulary: &Vocabulary, leniency: Leniency) -> Audit {
+            let mut calibrations = vec![];
+            let mut invalid = vec![];
+            for (line_number, line) in (1..).zip(self.input.iter()) {
+                let calibration = vocabulary.calibrate(line_number, line);
+                if !calibration.is_valid() {
+                    invalid.push(line_number);
+                    match leniency {
+                        Leniency::SkipInvalid => continue,
+                        Leniency::ZeroInvalid => (),
+                    }
+                }
+                calibrations.push(calibration);
+            }
+
+            Audit {
+                calibrations,
+                invalid,
+            }
+        }
+
+        // lines without digits add nothing
+        fn calibration_sum(&self, vocabulary: &Vocabulary) -> i32 {
+            self.audit(vocabulary, Leniency::SkipInvalid).total()
+        }
+    }
+
     impl Solution for Input {
         fn part_1(&self) -> Box<dyn Display> {
-            let answer = self.input.iter()
-                .map(|line| {
-                    let first = line.chars().filter(|c| c.is_numeric()).nth(0).unwrap();
-                    let last = line.chars().rev().filter(|c| c.is_numeric()).nth(0).unwrap();
-                    (first as i32 - '0' as i32) * 10 + (last as i32 - '0' as i32)
-                }).sum::<i32>();
+            let answer = self.calibration_sum(&Vocabulary::digits());
 
             Box::new(answer)
         }
 
         fn part_2(&self) -> Box<dyn Display> {
-            let forward_pattern = Regex::new("^.*?([0-9]|one|two|three|four|five|six|seven|eight|nine)").unwrap();
-            let backward_pattern = Regex::new("^.*?([0-9]|eno|owt|eerht|ruof|evif|xis|neves|thgie|enin)").unwrap();
-
-            let answer = self.input.iter().map(|line| {
-                let first = forward_pattern.captures(line).unwrap().get(1).unwrap().as_str();
-                let reversed = &line.chars().rev().collect::<String>();
-                let last = backward_pattern.captures(reversed).unwrap().get(1).unwrap().as_str();
-                to_num(first) * 10 + to_num(&last.chars().rev().collect::<String>())
-            })
-            .sum::<i32>();
+            let answer = self.calibration_sum(&Vocabulary::english());
 
             Box::new(answer)
         }
-    }
 
-    fn to_num(s: &str) -> i32 {
-        match s {
-            "one" => 1,
-            "two" => 2,
-            "three" => 3,
-            "four" => 4,
-            "five" => 5,
-            "six" => 6,
-            "seven" => 7,
-            "eight" => 8,
-            "nine" => 9,
-            _ => {
-                s.parse().unwrap()
-            },
+        // every line, with the lines that have no digits counted as zero
+        fn report(&self) -> Option<String> {
+            let mut report = String::new();
+            writeln!(report, "digits:\n{}", self.audit(&Vocabulary::digits(), Leniency::ZeroInvalid)).unwrap();
+            write!(report, "digits and words:\n{}", self.audit(&Vocabulary::english(), Leniency::ZeroInvalid)).unwrap();
+            Some(report)
         }
     }
 }
 
 mod day_02 {
     use std::collections::HashMap;
-    use std::fmt::Display;
-    use std::str::FromStr;
+    use std::fmt::{Display, Write};
     use crate::day::{Day, Solution};
+    use crate::parse::parse;
 
-    #[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
-    enum Colour { R, B, G }
+    type Colour = String;
 
-    impl FromStr for Colour {
-        type Err = ();
+    // how many cubes of each colour are in the bag. colours that aren't mentioned have none
+    type Bag = HashMap<Colour, i32>;
 
-        fn from_str(s: &str) -> Result<Self, Self::Err> {
-            match s {
-                "red" => Ok(Self::R),
-                "blue" => Ok(Self::B),
-                "green" => Ok(Self::G),
-                _ => Err(()),
-            }
-        }
-    }
+    const PUZZLE_BAG: [(&str, i32); 3] = [("red", 12), ("green", 13), ("blue", 14)];
 
     pub struct Day02;
     struct Input {
@@ -157,18 +717,19 @@ mod day_02 {
 
     impl Day for crate::day_02::Day02 {
         fn process_input(&self, input: &str) -> Box<dyn Solution> {
-            let input = input.lines()
-                .map(|line| {
-                    let (name, rest) = line.split_once(": ").unwrap();
-                    let games = rest.split("; ")
-                        .map(|g|
-                            g.split(", ").map(|b| {
-                                let (n, c) = b.split_once(" ").unwrap();
-                                (c.parse().unwrap(), n.parse().unwrap())
-                            }).collect()
-                        ).collect();
-                    (name.split_once(" ").unwrap().1.parse().unwrap(), games)
-                }).collect();
+            let input = parse(input, |p| p.lines(|p| {
+                p.tag("Game ")?;
+                let id = p.integer()?;
+                p.tag(": ")?;
+                let games = p.separated("; ", |p|
+                    p.separated(", ", |p| {
+                        let n = p.integer()?;
+                        p.tag(" ")?;
+                        Ok((p.word()?.to_owned(), n))
+                    })
+                )?;
+                Ok((id, games))
+            }));
 
             Box::new(Input {
                 input
@@ -176,54 +737,138 @@ mod day_02 {
         }
     }
 
+    fn bag_from(counts: &[(&str, i32)]) -> Bag {
+        counts.iter().map(|(colour, n)| (colour.to_string(), *n)).collect()
+    }
+
+    // colours in alphabetical order, so the output is stable
+    fn show_bag(bag: &Bag) -> String {
+        let mut counts: Vec<(&Colour, &i32)> = bag.iter().collect();
+        counts.sort();
+        counts.iter().map(|(colour, n)| format!("{} {}", n, colour)).collect::<Vec<_>>().join(", ")
+    }
+
+    // raises the bag's counts to at least the given ones
+    fn merge(mut bag: Bag, counts: impl IntoIterator<Item=(Colour, i32)>) -> Bag {
+        counts.into_iter().for_each(|(colour, n)| {
+            bag.entry(colour)
+                .and_modify(|old| *old = (*old).max(n))
+                .or_insert(n);
+        });
+        bag
+    }
+
+    impl Input {
+        fn game(&self, id: i32) -> Option<&Vec<Vec<(Colour, i32)>>> {
+            self.input.iter()
+                .find(|(i, _)| *i == id)
+                .map(|(_, rounds)| rounds)
+        }
+
+        fn is_possible(rounds: &[Vec<(Colour, i32)>], bag: &Bag) -> bool {
+            rounds.iter()
+                .flat_map(|r| r.iter())
+                .all(|(col, n)| n <= bag.get(col).unwrap_or(&0))
+        }
+
+        fn possible_games(&self, bag: &Bag) -> Vec<i32> {
+            self.input.iter()
+                .filter(|(_, rounds)| Self::is_possible(rounds, bag))
+                .map(|(i, _)| *i)
+                .collect()
+        }
+
+        fn minimal_bag(rounds: &[Vec<(Colour, i32)>]) -> Bag {
+            merge(Bag::new(), rounds.iter().flatten().cloned())
+        }
+
+        fn minimal_bags(&self) -> Vec<(i32, Bag)> {
+            self.input.iter()
+                .map(|(i, rounds)| (*i, Self::minimal_bag(rounds)))
+                .collect()
+        }
+
+        // the smallest bag that all of the given games could have been played with, or
+        // None if one of them isn't a game
+        fn smallest_bag_for(&self, ids: &[i32]) -> Option<Bag> {
+            ids.iter().try_fold(Bag::new(), |bag, id| {
+                let rounds = self.game(*id)?;
+                Some(merge(bag, Self::minimal_bag(rounds)))
+            })
+        }
+    }
+
     impl Solution for Input {
         fn part_1(&self) -> Box<dyn Display> {
-            let limits: HashMap::<Colour, i32> = HashMap::from([(Colour::R, 12), (Colour::G, 13), (Colour::B, 14)]);
-            let answer: i32 = self.input.iter()
-                .filter_map(|(i, rounds)| {
-                    let illegal = rounds.iter()
-                        .flat_map(|r| r.iter())
-                        .any(|(col, n)| n > limits.get(col).unwrap());
-                    if !illegal { Some(i) } else { None }
-                })
-                .sum();
+            let answer: i32 = self.possible_games(&bag_from(&PUZZLE_BAG)).iter().sum();
 
             Box::new(answer)
         }
 
         fn part_2(&self) -> Box<dyn Display> {
-            let answer: i32 = self.input.iter()
-                .map(|(_, rounds)| {
-                    let mut mins = HashMap::new();
-                    rounds.iter()
-                        .flat_map(|r| r.iter())
-                        .for_each(|(col, n)| {
-                            mins.entry(*col)
-                                .and_modify(|old: &mut i32| *old = (*old).max(*n))
-                                .or_insert(*n);
-                        });
-                    mins.values().product::<i32>()
-                }).sum();
+            let answer: i32 = self.minimal_bags().iter()
+                .map(|(_, bag)| bag.values().product::<i32>())
+                .sum();
 
             Box::new(answer)
         }
+
+        fn report(&self) -> Option<String> {
+            let bag = bag_from(&PUZZLE_BAG);
+            let possible = self.possible_games(&bag);
+            let mut report = String::new();
+            for (id, minimal) in self.minimal_bags() {
+                let verdict = if possible.contains(&id) { "possible" } else { "impossible" };
+                writeln!(report, "game {}: needs {} ({})", id, show_bag(&minimal), verdict).unwrap();
+            }
+            writeln!(report, "possible with {}: {} games", show_bag(&bag), possible.len()).unwrap();
+            if let Some(smallest) = self.smallest_bag_for(&possible) {
+                writeln!(report, "smallest bag for those games: {}", show_bag(&smallest)).unwrap();
+            }
+            Some(report)
+        }
     }
 }
 
 mod day_03 {
     use std::collections::{HashMap, HashSet};
-    use std::fmt::Display;
+    use std::fmt::{Display, Write};
+    use std::io::IsTerminal;
     use crate::day::{Day, Solution};
 
     pub struct Day03;
 
+    // symbols and where they are
+    type Adjacents = HashSet<((i32, i32), char)>;
+
+    #[derive(Debug)]
+    struct Number {
+        value: i32,
+        // row, and the inclusive range of columns the digits cover
+        y: i32,
+        x_span: (i32, i32),
+        adjacents: Adjacents,
+    }
+
+    impl Number {
+        // coordinates of the first and last digit
+        fn span(&self) -> ((i32, i32), (i32, i32)) {
+            ((self.x_span.0, self.y), (self.x_span.1, self.y))
+        }
+    }
+
     struct Input {
-        // numbers and their adjacent characters
-        numbers: Vec<(i32, HashSet<((i32, i32), char)>)>
+        schematic: Vec<Vec<char>>,
+        numbers: Vec<Number>,
+        symbols: HashMap<(i32, i32), char>,
+        // indices into `numbers` for each symbol's adjacent numbers
+        symbol_numbers: HashMap<(i32, i32), Vec<usize>>,
     }
 
     impl Day for Day03 {
         fn process_input(&self, input: &str) -> Box<dyn Solution> {
+            let schematic = input.lines().map(|line| line.chars().collect()).collect();
+
             let input: HashMap<(i32, i32), char> = input.lines()
                 .enumerate()
                 .flat_map(|(y, line)|
@@ -236,322 +881,659 @@ mod day_03 {
             let x_max = input.keys().max_by_key(|p| p.0).unwrap().0;
             let y_max = input.keys().max_by_key(|p| p.1).unwrap().1;
 
-            let mut adjacent_symbols: HashMap<(i32, i32), HashSet<((i32, i32), char)>> = HashMap::new();
-            input.iter().for_each(|(&(x, y), &c)|
-                    if (c < '0' || c > '9') && c != '.' {
-                        vec![
-                            (x - 1 ,y - 1),
-                            (x - 1, y    ),
-                            (x - 1, y + 1),
-                            (x     ,y - 1),
-                            (x    , y + 1),
-                            (x + 1 ,y - 1),
-                            (x + 1, y    ),
-                            (x + 1, y + 1)
-                        ].iter().for_each(|coord| {
-                            let e = adjacent_symbols.entry(*coord).or_insert_with(|| HashSet::new());
-                            e.insert(((x, y), c));
-                        })
-                    }
-                );
+            let symbols: HashMap<(i32, i32), char> = input.iter()
+                .filter(|(_, &c)| !c.is_ascii_digit() && c != '.')
+                .map(|(p, c)| (*p, *c))
+                .collect();
+
+            let mut adjacent_symbols: HashMap<(i32, i32), Adjacents> = HashMap::new();
+            symbols.iter().for_each(|(&(x, y), &c)|
+                [
+                    (x - 1 ,y - 1),
+                    (x - 1, y    ),
+                    (x - 1, y + 1),
+                    (x     ,y - 1),
+                    (x    , y + 1),
+                    (x + 1 ,y - 1),
+                    (x + 1, y    ),
+                    (x + 1, y + 1)
+                ].iter().for_each(|coord| {
+                    let e = adjacent_symbols.entry(*coord).or_default();
+                    e.insert(((x, y), c));
+                })
+            );
 
             let mut numbers = vec![];
 
-            let mut running: Option<i32> = None;
-            let mut adjacents: Option<HashSet<((i32, i32), char)>> = None;
+            // value, first column and adjacent symbols of the number being read
+            let mut running: Option<(i32, i32, Adjacents)> = None;
 
             let empty_set = HashSet::new();
 
             for y in 0..=y_max {
-                for x in 0..=x_max {
-                    let c = *input.get(&(x, y)).unwrap();
-                    match c {
-                        '0' ..= '9' => {
-                            adjacents = Some(adjacents.unwrap_or_else(|| HashSet::new()));
-                            adjacents.as_mut().unwrap().extend(adjacent_symbols.get(&(x, y)).unwrap_or_else(|| &empty_set));
-                            running = Some(running.unwrap_or(0) * 10 + c as i32 - '0' as i32);
+                for x in 0..=x_max + 1 {
+                    match input.get(&(x, y)) {
+                        Some(&c) if c.is_ascii_digit() => {
+                            let (value, _, adjacents) = running.get_or_insert_with(|| (0, x, HashSet::new()));
+                            adjacents.extend(adjacent_symbols.get(&(x, y)).unwrap_or(&empty_set));
+                            *value = *value * 10 + c as i32 - '0' as i32;
                         }
                         _ => {
-                            if running.is_some() {
-                                numbers.push((running.unwrap(), adjacents.clone().unwrap()));
+                            if let Some((value, start, adjacents)) = running.take() {
+                                numbers.push(Number {
+                                    value,
+                                    y,
+                                    x_span: (start, x - 1),
+                                    adjacents,
+                                });
                             }
-                            running = None;
-                            adjacents = None;
                         }
                     }
                 }
-                if running.is_some() {
-                    numbers.push((running.unwrap(), adjacents.clone().unwrap()));
-                }
-                running = None;
-                adjacents = None;
             }
 
+            let mut symbol_numbers: HashMap<(i32, i32), Vec<usize>> = symbols.keys()
+                .map(|p| (*p, vec![]))
+                .collect();
+            numbers.iter().enumerate()
+                .flat_map(|(i, number)| number.adjacents.iter().map(move |(coord, _)| (i, coord)))
+                .for_each(|(i, coord)| symbol_numbers.get_mut(coord).unwrap().push(i));
+
             Box::new(Input {
-                numbers
+                schematic,
+                numbers,
+                symbols,
+                symbol_numbers,
             })
         }
     }
 
+    impl Input {
+        fn part_numbers(&self) -> impl Iterator<Item=&Number> {
+            self.numbers.iter().filter(|n| !n.adjacents.is_empty())
+        }
+
+        // numbers that aren't next to any symbol
+        fn isolated_numbers(&self) -> impl Iterator<Item=&Number> {
+            self.numbers.iter().filter(|n| n.adjacents.is_empty())
+        }
+
+        fn numbers_adjacent_to(&self, symbol: char) -> impl Iterator<Item=&Number> {
+            self.numbers.iter().filter(move |n| n.adjacents.iter().any(|(_, c)| *c == symbol))
+        }
+
+        // each `symbol` with exactly `arity` adjacent numbers, with those numbers
+        fn symbols_with_arity(&self, symbol: char, arity: usize) -> Vec<((i32, i32), Vec<&Number>)> {
+            self.symbol_numbers.iter()
+                .filter(|(coord, nums)| self.symbols[coord] == symbol && nums.len() == arity)
+                .map(|(coord, nums)| (*coord, nums.iter().map(|i| &self.numbers[*i]).collect()))
+                .collect()
+        }
+
+        fn gear_ratios(&self, symbol: char, arity: usize) -> Vec<i32> {
+            self.symbols_with_arity(symbol, arity).iter()
+                .map(|(_, nums)| nums.iter().map(|n| n.value).product())
+                .collect()
+        }
+    }
+
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    enum Highlight {
+        // part numbers green, other numbers red, gears yellow
+        Ansi,
+        // [part numbers], (other numbers) and {gears}. this widens the lines, so columns no
+        // longer line up
+        Brackets,
+    }
+
+    impl Highlight {
+        fn part_number(&self, s: &str) -> String {
+            match self {
+                Highlight::Ansi => format!("\x1b[32m{}\x1b[0m", s),
+                Highlight::Brackets => format!("[{}]", s),
+            }
+        }
+
+        fn other_number(&self, s: &str) -> String {
+            match self {
+                Highlight::Ansi => format!("\x1b[31m{}\x1b[0m", s),
+                Highlight::Brackets => format!("({})", s),
+            }
+        }
+
+        fn gear(&self, s: &str) -> String {
+            match self {
+                Highlight::Ansi => format!("\x1b[1;33m{}\x1b[0m", s),
+                Highlight::Brackets => format!("{{{}}}", s),
+            }
+        }
+    }
+
+    impl Input {
+        // the schematic with part numbers, other numbers and gears (as `gear` symbols with
+        // `arity` adjacent numbers) each highlighted
+        fn render(&self, highlight: Highlight, gear: char, arity: usize) -> String {
+            let number_starts: HashMap<(i32, i32), &Number> = self.numbers.iter()
+                .map(|n| ((n.x_span.0, n.y), n))
+                .collect();
+            let gears: HashSet<(i32, i32)> = self.symbols_with_arity(gear, arity).into_iter()
+                .map(|(coord, _)| coord)
+                .collect();
+
+            let mut rendered = String::new();
+            for (y, row) in (0..).zip(self.schematic.iter()) {
+                let mut x = 0;
+                while x < row.len() as i32 {
+                    if let Some(number) = number_starts.get(&(x, y)) {
+                        let digits: String = row[x as usize..=number.x_span.1 as usize].iter().collect();
+                        if number.adjacents.is_empty() {
+                            rendered.push_str(&highlight.other_number(&digits));
+                        } else {
+                            rendered.push_str(&highlight.part_number(&digits));
+                        }
+                        x = number.x_span.1 + 1;
+                    } else {
+                        let c = row[x as usize].to_string();
+                        if gears.contains(&(x, y)) {
+                            rendered.push_str(&highlight.gear(&c));
+                        } else {
+                            rendered.push_str(&c);
+                        }
+                        x += 1;
+                    }
+                }
+                rendered.push('\n');
+            }
+            rendered
+        }
+    }
+
     impl Solution for Input {
         fn part_1(&self) -> Box<dyn Display> {
-            let answer: i32 = self.numbers.iter()
-                .filter(|(_, symbols)| symbols.len() > 0)
-                .map(|(num, _)| num)
+            let answer: i32 = self.part_numbers()
+                .map(|n| n.value)
                 .sum();
 
             Box::new(answer)
         }
 
         fn part_2(&self) -> Box<dyn Display> {
-            let mut gears: HashMap::<(i32, i32), Vec<i32>> = HashMap::new();
-
-            self.numbers.iter()
-                .flat_map(|(num, adjacents)|
-                    adjacents.into_iter().map(move |(coord, _)| (num, coord)))
-                .for_each(|(num, coord)| {
-                    let nums = gears.entry(*coord).or_insert(vec![]);
-                    nums.push(*num);
-                });
+            let answer: i32 = self.gear_ratios('*', 2).iter().sum();
+
+            Box::new(answer)
+        }
+
+        fn report(&self) -> Option<String> {
+            // colours only make sense on a terminal
+            let highlight = if std::io::stdout().is_terminal() { Highlight::Ansi } else { Highlight::Brackets };
+            let mut report = self.render(highlight, '*', 2);
+            for number in self.isolated_numbers() {
+                let (first, last) = number.span();
+                writeln!(report, "{} at {:?}..={:?} isn't next to a symbol", number.value, first, last).unwrap();
+            }
+            writeln!(report, "{} numbers are next to a '*'", self.numbers_adjacent_to('*').count()).unwrap();
+            Some(report)
+        }
+    }
+}
+
+mod day_04 {
+    use std::collections::HashSet;
+    use std::fmt::{Display, Write};
+    use crate::day::{Day, Solution};
+    use crate::parse::{parse, Parser};
+
+    pub struct Day04;
+
+    struct Input {
+        // card ids, winning numbers and our numbers
+        input: Vec<(i32, HashSet<i32>, Vec<i32>)>,
+    }
+
+    impl Day for Day04 {
+        fn process_input(&self, input: &str) -> Box<dyn Solution> {
+            let input = parse(input, |p| p.lines(|p| {
+                p.tag("Card")?;
+                p.spaces();
+                let id = p.integer()?;
+                p.tag(":")?;
+                let winners: Vec<i32> = p.spaced(Parser::integer)?;
+                p.tag("|")?;
+                let ours: Vec<i32> = p.spaced(Parser::integer)?;
+
+                Ok((
+                    id,
+                    HashSet::from_iter(winners),
+                    ours
+                ))
+            }));
+
+            Box::new(Input {
+                input
+            })
+        }
+    }
+
+    #[derive(Debug)]
+    struct CardReport {
+        id: i32,
+        matches: usize,
+        points: i32,
+        // the original plus all the won copies
+        copies: u64,
+        // earlier cards that won copies of this one, and how many each won
+        won_from: Vec<(i32, u64)>,
+    }
+
+    impl Input {
+        // cards are numbered from 1 in order, so each card's index is its id - 1
+        fn trace(&self) -> Vec<CardReport> {
+            let matches: Vec<usize> = self.input.iter()
+                .map(|(_id, winners, ours)| ours.iter().filter(|n| winners.contains(n)).count())
+                .collect();
+
+            let mut copies: Vec<u64> = vec![1; self.input.len()];
+            let mut won_from: Vec<Vec<(i32, u64)>> = vec![vec![]; self.input.len()];
+
+            for i in 0..self.input.len() {
+                let won = i + 1..(i + 1 + matches[i]).min(self.input.len());
+                for j in won {
+                    copies[j] += copies[i];
+                    won_from[j].push((self.input[i].0, copies[i]));
+                }
+            }
+
+            self.input.iter()
+                .zip(matches)
+                .zip(copies.into_iter().zip(won_from))
+                .map(|(((id, _, _), matches), (copies, won_from))| CardReport {
+                    id: *id,
+                    matches,
+                    points: if matches > 0 { 2i32.pow(matches as u32 - 1) } else { 0 },
+                    copies,
+                    won_from,
+                })
+                .collect()
+        }
 
-            let answer: i32 = gears.into_iter()
-                .filter(|(_, nums)| nums.len() == 2)
-                .map(|(_, nums)| nums.iter().product::<i32>())
+        // where the copies of a card came from, looked up in an existing trace
+        fn provenance(trace: &[CardReport], id: i32) -> Option<&[(i32, u64)]> {
+            trace.iter()
+                .find(|report| report.id == id)
+                .map(|report| report.won_from.as_slice())
+        }
+    }
+
+    impl Solution for Input {
+        fn part_1(&self) -> Box<dyn Display> {
+            let answer: i32 = self.trace().iter()
+                .map(|report| report.points)
                 .sum();
 
             Box::new(answer)
         }
+
+        fn part_2(&self) -> Box<dyn Display> {
+            let answer: u64 = self.trace().iter()
+                .map(|report| report.copies)
+                .sum();
+
+            Box::new(answer)
+        }
+
+        fn report(&self) -> Option<String> {
+            let trace = self.trace();
+            let mut report = String::new();
+            for card in &trace {
+                writeln!(report, "card {}: {} matches, {} points, {} copies", card.id, card.matches, card.points, card.copies).unwrap();
+            }
+            if let Some(most) = trace.iter().max_by_key(|card| card.copies) {
+                let sources: Vec<String> = Self::provenance(&trace, most.id).unwrap().iter()
+                    .map(|(id, copies)| format!("{} from card {}", copies, id))
+                    .collect();
+                writeln!(report, "card {} has the most copies: {}", most.id, sources.join(", ")).unwrap();
+            }
+            Some(report)
+        }
     }
 }
 
-mod day_04 {
-    use std::collections::{HashMap, HashSet};
-    use std::fmt::Display;
-    use regex::Regex;
-    use crate::day::{Day, Solution};
+mod day_05 {
+    use std::fmt::{Display, Formatter, Write};
+    use crate::day::{Day, Solution};
+    use crate::parse::{parse, Parser};
+
+    pub struct Day05;
+
+    #[derive(Debug, Clone, Copy)]
+    struct Conversion {
+        from_start: i64,
+        to_start: i64,
+        length: i64,
+    }
+
+    // a function on all of i64 that shifts each of a series of ranges by some offset
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    struct PiecewiseMap {
+        // (start, offset) pairs, sorted by start. from each start up to the next one, x maps
+        // to x + offset. the first start is always i64::MIN
+        segments: Vec<(i64, i64)>,
+    }
+
+    impl PiecewiseMap {
+        fn identity() -> Self {
+            PiecewiseMap {
+                segments: vec![(i64::MIN, 0)],
+            }
+        }
+
+        // numbers not covered by any of the conversions map to themselves
+        fn from_conversions(conversions: &[Conversion]) -> Self {
+            let mut conversions = conversions.to_vec();
+            conversions.sort_by_key(|conv| conv.from_start);
+
+            let mut segments = vec![];
+            let mut next = i64::MIN;
+            for conv in conversions.iter().filter(|conv| conv.length > 0) {
+                assert!(conv.from_start >= next, "overlapping conversions at {}", conv.from_start);
+                if conv.from_start > next {
+                    segments.push((next, 0));
+                }
+                segments.push((conv.from_start, conv.to_start - conv.from_start));
+                next = conv.from_start + conv.length;
+            }
+            segments.push((next, 0));
+
+            Self::normalised(segments)
+        }
+
+        // merges neighbouring segments with the same offset
+        fn normalised(segments: Vec<(i64, i64)>) -> Self {
+            let mut merged: Vec<(i64, i64)> = vec![];
+            for (start, offset) in segments {
+                match merged.last_mut() {
+                    Some(last) if last.0 == start => last.1 = offset,
+                    Some(last) if last.1 == offset => {},
+                    _ => merged.push((start, offset)),
+                }
+            }
+            PiecewiseMap {
+                segments: merged,
+            }
+        }
+
+        // (start, inclusive end, offset) for each segment
+        fn ranges(&self) -> impl Iterator<Item=(i64, i64, i64)> + '_ {
+            self.segments.iter()
+                .enumerate()
+                .map(|(i, (start, offset))| {
+                    let end = self.segments.get(i + 1).map_or(i64::MAX, |next| next.0 - 1);
+                    (*start, end, *offset)
+                })
+        }
+
+        // the segments overlapping the inclusive range lo..=hi, clipped to it
+        fn clipped(&self, lo: i64, hi: i64) -> impl Iterator<Item=(i64, i64, i64)> + '_ {
+            self.ranges()
+                .filter(move |(start, end, _)| *start <= hi && *end >= lo)
+                .map(move |(start, end, offset)| (start.max(lo), end.min(hi), offset))
+        }
+
+        fn apply(&self, x: i64) -> i64 {
+            let i = self.segments.partition_point(|(start, _)| *start <= x) - 1;
+            x + self.segments[i].1
+        }
 
-    pub struct Day04;
+        // the image of a set of inclusive ranges, as sorted and merged inclusive ranges
+        fn apply_ranges(&self, ranges: &[(i64, i64)]) -> Vec<(i64, i64)> {
+            let mut images: Vec<(i64, i64)> = ranges.iter()
+                .flat_map(|(lo, hi)| self.clipped(*lo, *hi))
+                .map(|(start, end, offset)| (start + offset, end + offset))
+                .collect();
+            images.sort();
 
-    struct Input {
-        // numbers and their adjacent characters
-        input: Vec<(i32, HashSet<i32>, Vec<i32>)>,
-    }
+            let mut merged: Vec<(i64, i64)> = vec![];
+            for (start, end) in images {
+                match merged.last_mut() {
+                    Some(last) if start <= last.1.saturating_add(1) => last.1 = last.1.max(end),
+                    _ => merged.push((start, end)),
+                }
+            }
+            merged
+        }
 
-    impl Day for Day04 {
-        fn process_input(&self, input: &str) -> Box<dyn Solution> {
-            let regex = Regex::new("^Card *([0-9]*): ([^|]*)\\|(.*)$").unwrap();
+        // this map followed by `next`, as a single map
+        fn then(&self, next: &PiecewiseMap) -> PiecewiseMap {
+            let segments = self.ranges()
+                .flat_map(|(start, end, offset)|
+                    next.clipped(start + offset, end + offset)
+                        .map(move |(image_start, _, next_offset)| (image_start - offset, offset + next_offset))
+                )
+                .collect();
 
-            let input = input.lines()
-                .map(|line| {
-                    let captures = regex.captures(line).unwrap();
-
-                    let id = captures.get(1).unwrap().as_str().parse().unwrap();
-                    let winners: Vec<i32> = captures.get(2).unwrap().as_str()
-                        .split(' ')
-                        .filter(|s| !s.is_empty())
-                        .map(|s| s.parse().unwrap())
-                        .collect();
-                    let ours: Vec<i32> = captures.get(3).unwrap().as_str()
-                        .split(' ')
-                        .filter(|s| !s.is_empty())
-                        .map(|s| s.parse().unwrap())
-                        .collect();
+            Self::normalised(segments)
+        }
 
-                    (
-                        id,
-                        HashSet::from_iter(winners),
-                        ours
-                    )
-                }).collect();
+        // every x that maps to y
+        fn preimage(&self, y: i64) -> Vec<i64> {
+            self.ranges()
+                .filter(|(start, end, offset)| *start <= y - offset && y - offset <= *end)
+                .map(|(_, _, offset)| y - offset)
+                .collect()
+        }
 
-            Box::new(Input {
-                input
-            })
+        // the inverse map, if no two numbers map to the same place
+        fn invert(&self) -> Option<PiecewiseMap> {
+            let mut images: Vec<(i64, i64, i64)> = self.ranges()
+                .map(|(start, end, offset)| (start + offset, end + offset, -offset))
+                .collect();
+            images.sort();
+            if images.windows(2).any(|w| w[0].1 >= w[1].0) {
+                return None;
+            }
+            Some(Self::normalised(images.into_iter().map(|(start, _, offset)| (start, offset)).collect()))
         }
     }
 
-    impl Solution for Input {
-        fn part_1(&self) -> Box<dyn Display> {
-            let answer: i32 = self.input.iter()
-                .map(|(_id, winners, ours)| {
-                    let matches = ours.iter().filter(|n| winners.contains(n)).count();
-                    if matches > 0 { 2i32.pow(matches as u32 - 1) } else { 0 }
-                }).sum();
+    struct CategoryMap {
+        from: String,
+        to: String,
+        map: PiecewiseMap,
+    }
 
-            Box::new(answer)
-        }
+    #[derive(Debug)]
+    enum RouteError {
+        NoRoute { from: String, to: String },
+        Ambiguous { from: String, to: String, routes: usize },
+    }
 
-        fn part_2(&self) -> Box<dyn Display> {
-            let winnings_per_card: HashMap<i32, Vec<i32>> = self.input.iter()
-                .map(|(id, winners, ours)| {
-                    let matches = ours.iter().filter(|n| winners.contains(n)).count();
-                    let winnings = (1..=matches).into_iter()
-                        .map(|i| id + i as i32)
-                        .collect();
-                    (*id, winnings)
-                }).collect();
+    impl Display for RouteError {
+        fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+            match self {
+                RouteError::NoRoute { from, to } => write!(f, "no maps lead from {} to {}", from, to),
+                RouteError::Ambiguous { from, to, routes } =>
+                    write!(f, "{} different routes lead from {} to {}", routes, from, to),
+            }
+        }
+    }
 
-            let mut total_cards: HashMap<i32, i32> = self.input.iter()
-                .map(|(id, _, _)| (*id, 1))
-                .collect();
+    // the maps between named categories, in the order they were given
+    struct Almanac {
+        maps: Vec<CategoryMap>,
+    }
 
-            for i in 1..=self.input.last().unwrap().0 {
-                let n = *total_cards.get(&i).unwrap_or(&0);
-                let winnings = winnings_per_card.get(&i);
-                if let Some(winnings) = winnings {
-                    winnings.iter().for_each(|c| {
-                        *(total_cards.get_mut(c).unwrap()) += n;
-                    });
+    impl Almanac {
+        // every route from one category to another that doesn't revisit a category, as
+        // indices into `maps`
+        fn routes(&self, from: &str, to: &str) -> Vec<Vec<usize>> {
+            fn extend<'a>(almanac: &'a Almanac, at: &'a str, to: &str, visited: &mut Vec<&'a str>, route: &mut Vec<usize>, routes: &mut Vec<Vec<usize>>) {
+                if at == to {
+                    routes.push(route.clone());
+                    return;
+                }
+                visited.push(at);
+                for (i, map) in almanac.maps.iter().enumerate() {
+                    if map.from == at && !visited.contains(&map.to.as_str()) {
+                        route.push(i);
+                        extend(almanac, &map.to, to, visited, route, routes);
+                        route.pop();
+                    }
                 }
+                visited.pop();
             }
 
-            let answer: i32 = total_cards.values().sum();
-
-            Box::new(answer)
+            let mut routes = vec![];
+            extend(self, from, to, &mut vec![], &mut vec![], &mut routes);
+            routes
         }
-    }
-}
 
-mod day_05 {
-    use std::collections::BTreeMap;
-    use std::fmt::Display;
-    use crate::day::{Day, Solution};
+        // the single map that converts `from` numbers into `to` numbers
+        fn route(&self, from: &str, to: &str) -> Result<PiecewiseMap, RouteError> {
+            let routes = self.routes(from, to);
+            match &routes[..] {
+                [] => Err(RouteError::NoRoute { from: from.to_owned(), to: to.to_owned() }),
+                [route] => Ok(route.iter()
+                    .fold(PiecewiseMap::identity(), |map, i| map.then(&self.maps[*i].map))),
+                _ => Err(RouteError::Ambiguous { from: from.to_owned(), to: to.to_owned(), routes: routes.len() }),
+            }
+        }
 
-    pub struct Day05;
+        // maps that don't convert from what the previous map converted to
+        fn out_of_order(&self) -> Vec<(&str, &str)> {
+            self.maps.windows(2)
+                .filter(|w| w[0].to != w[1].from)
+                .map(|w| (w[1].from.as_str(), w[1].to.as_str()))
+                .collect()
+        }
 
-    #[derive(Debug, Clone, Copy)]
-    struct Conversion {
-        from_start: i64,
-        to_start: i64,
-        length: i64,
+        // categories that convert into more than one other category
+        fn branches(&self) -> Vec<(&str, Vec<&str>)> {
+            let mut branches: Vec<(&str, Vec<&str>)> = vec![];
+            for map in &self.maps {
+                match branches.iter_mut().find(|(from, _)| *from == map.from) {
+                    Some((_, tos)) => tos.push(&map.to),
+                    None => branches.push((&map.from, vec![&map.to])),
+                }
+            }
+            branches.retain(|(_, tos)| tos.len() > 1);
+            branches
+        }
     }
 
     struct Input {
         seeds: Vec<i64>,
-        mappings: Vec<BTreeMap<i64, Conversion>>,
+        almanac: Almanac,
     }
 
     impl Day for Day05 {
         fn process_input(&self, input: &str) -> Box<dyn Solution> {
-            let mut paragraphs = input.split("\n\n");
-
-            let seeds: Vec<i64> = paragraphs.next().unwrap()[7..].split(' ').map(|s| s.parse().unwrap()).collect();
-
-            // let mappingRegex = Regex::new("^([^-]*)-to-([^-]*) map:$").unwrap();
-            let mappings = paragraphs.map(|para|
-                para.lines()
-                    .skip(1)
-                    .map(|line| {
-                        let mut split = line.split(' ');
-                        let conv = Conversion {
-                            to_start: split.next().unwrap().parse().unwrap(),
-                            from_start: split.next().unwrap().parse().unwrap(),
-                            length: split.next().unwrap().parse().unwrap(),
-                        };
-                        (conv.from_start, conv)
-                    }).collect()
-            ).collect();
+            let (seeds, maps) = parse(input, |p| {
+                let seeds = p.section(|p| {
+                    p.tag("seeds:")?;
+                    p.spaced(Parser::integer)
+                })?;
+
+                let maps = p.sections(|p| {
+                    let (from, to) = p.line(|p| {
+                        let from = p.word()?.to_owned();
+                        p.tag("-to-")?;
+                        let to = p.word()?.to_owned();
+                        p.tag(" map:")?;
+                        Ok((from, to))
+                    })?;
+                    let conversions = p.lines(|p| {
+                        let to_start = p.integer()?;
+                        p.tag(" ")?;
+                        let from_start = p.integer()?;
+                        p.tag(" ")?;
+                        let length = p.integer()?;
+                        Ok(Conversion {
+                            to_start,
+                            from_start,
+                            length,
+                        })
+                    })?;
+                    Ok(CategoryMap {
+                        from,
+                        to,
+                        map: PiecewiseMap::from_conversions(&conversions),
+                    })
+                })?;
+
+                Ok((seeds, maps))
+            });
 
             Box::new(Input {
                 seeds,
-                mappings,
+                almanac: Almanac {
+                    maps,
+                },
             })
         }
     }
 
-    impl Solution for Input {
-        fn part_1(&self) -> Box<dyn Display> {
-            let mut numbers = self.seeds.clone();
+    impl Input {
+        fn seed_to_location(&self) -> PiecewiseMap {
+            self.almanac.route("seed", "location").unwrap_or_else(|e| panic!("{}", e))
+        }
 
-            for mapping in &self.mappings {
-                numbers.iter_mut()
-                    .for_each(|x| {
-                        let conv = mapping.values()
-                            .filter(|conv| *x >= conv.from_start && (*x - (conv.length - 1)) <= conv.from_start)
-                            .nth(0)
-                            .unwrap_or(&Conversion { from_start: 0, to_start: 0, length: 0 });
+        fn seed_ranges(&self) -> Vec<(i64, i64)> {
+            self.seeds.chunks(2).map(|ss| (ss[0], ss[0] + ss[1] - 1)).collect()
+        }
 
-                        *x = *x - conv.from_start + conv.to_start;
-                    })
-            }
+        // seeds from the seed ranges that end up at the given location
+        fn seeds_for_location(&self, location: i64) -> Vec<i64> {
+            let seed_ranges = self.seed_ranges();
+            self.seed_to_location().preimage(location).into_iter()
+                .filter(|seed| seed_ranges.iter().any(|(lo, hi)| lo <= seed && seed <= hi))
+                .collect()
+        }
+    }
 
-            let answer: i64 = numbers.into_iter().min().unwrap();
+    impl Solution for Input {
+        fn part_1(&self) -> Box<dyn Display> {
+            let almanac = self.seed_to_location();
+            let answer: i64 = self.seeds.iter().map(|seed| almanac.apply(*seed)).min().unwrap();
 
             Box::new(answer)
         }
 
         fn part_2(&self) -> Box<dyn Display> {
-            let seed_ranges = self.seeds.chunks(2).map(|ss| (ss[0], ss[0] + ss[1] - 1)).collect();
+            let locations = self.seed_to_location().apply_ranges(&self.seed_ranges());
+            let answer: i64 = locations[0].0;
 
-            let mut vec_0: Vec<(i64, i64)> = seed_ranges;
-            let mut vec_1: Vec<(i64, i64)> = vec![];
-
-            let input_ranges = &mut vec_0;
-            let output_ranges = &mut vec_1;
-
-            for mapping in &self.mappings {
-                'next_input_range: for (mut start, end) in input_ranges.iter() {
-                    if start > *end {
-                        break 'next_input_range;
-                    }
-                    let possibles = mapping.range(0..=start).last().into_iter().chain(mapping.range(start..=*end)).map(|(_, c)| c);
-                    'next_possibility: for possible in possibles {
-                        let range_start = possible.from_start;
-                        let range_end = range_start + possible.length - 1;
-
-                        let range_diff = possible.to_start - possible.from_start;
-
-                        if start > range_end {
-                            // a   ()
-                            // r ()
-                            continue 'next_possibility;
-                        } else if start >= range_start && *end > range_end {
-                            // a (   )
-                            // r ( )
-                            output_ranges.push((start + range_diff, range_end + range_diff));
-                            start = range_end + 1;
-                        } else if start >= range_start && *end <= range_end {
-                            // a (  )
-                            // r (    )
-                            output_ranges.push((start + range_diff, *end + range_diff));
-                            continue 'next_input_range;
-                        } else if start < range_start && *end <= range_end {
-                            // a (   )
-                            // r   ( )
-                            output_ranges.push((start, range_start - 1));
-                            output_ranges.push((range_start + range_diff, *end + range_diff));
-                            continue 'next_input_range;
-                        } else if start < range_start && *end > range_end {
-                            // a (     )
-                            // r   ( )
-                            output_ranges.push((start, range_start - 1));
-                            output_ranges.push((range_start + range_diff, range_end + range_diff));
-                            start = range_end + 1;
-                        }
-                    }
-                    if start <= *end {
-                        output_ranges.push((start, *end));
-                    }
-                }
+            Box::new(answer)
+        }
 
-                std::mem::swap(input_ranges, output_ranges);
-                output_ranges.truncate(0);
+        fn report(&self) -> Option<String> {
+            let almanac = self.seed_to_location();
+            let mut report = String::new();
+            for (from, to) in self.almanac.out_of_order() {
+                writeln!(report, "{}-to-{} is out of order", from, to).unwrap();
+            }
+            for (from, tos) in self.almanac.branches() {
+                writeln!(report, "{} branches to {}", from, tos.join(", ")).unwrap();
             }
+            writeln!(report, "seed-to-location has {} segments", almanac.segments.len()).unwrap();
 
-            let answer: i64 = input_ranges.iter()
-                .min_by_key(|r| r.0)
-                .unwrap()
-                .0;
+            let nearest = self.seeds.iter().map(|seed| almanac.apply(*seed)).min().unwrap();
+            match almanac.invert() {
+                Some(inverse) => writeln!(report, "location {} is seed {}", nearest, inverse.apply(nearest)).unwrap(),
+                None => writeln!(report, "seed-to-location can't be inverted").unwrap(),
+            }
 
-            Box::new(answer)
+            let nearest_in_ranges = almanac.apply_ranges(&self.seed_ranges())[0].0;
+            writeln!(report, "location {} comes from seeds {:?} in the seed ranges", nearest_in_ranges, self.seeds_for_location(nearest_in_ranges)).unwrap();
+            Some(report)
         }
     }
 }
 
 mod day_06 {
-    use std::fmt::Display;
-    use num::BigInt;
+    use std::fmt::{Display, Formatter, Write};
+    use num::{BigInt, Integer, One, Signed, Zero};
     use crate::day::{Day, Solution};
+    use crate::math::{exact_sqrt, quadratic_negative_range};
 
     pub struct Day06;
     struct Input {
@@ -574,39 +1556,215 @@ mod day_06 {
         }
     }
 
+    // how a boat turns button-holding into speed
+    #[derive(Debug, Clone)]
+    struct Boat {
+        // speed gained per ms held
+        acceleration: BigInt,
+        top_speed: Option<BigInt>,
+    }
+
+    #[derive(Debug)]
+    struct RaceAnalysis {
+        time: BigInt,
+        record: BigInt,
+        // inclusive range of hold times that beat the record
+        winning_holds: Option<(BigInt, BigInt)>,
+        best_hold: BigInt,
+        best_distance: BigInt,
+        // hold times that exactly match the record
+        ties: Vec<BigInt>,
+    }
+
+    impl RaceAnalysis {
+        fn ways_to_win(&self) -> BigInt {
+            self.winning_holds.as_ref().map_or(BigInt::zero(), |(lo, hi)| hi - lo + 1)
+        }
+
+        // how far past the record the best hold goes, negative if the race can't be won
+        fn margin(&self) -> BigInt {
+            &self.best_distance - &self.record
+        }
+    }
+
+    impl Display for RaceAnalysis {
+        fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+            write!(f, "{} ms, record {}: ", self.time, self.record)?;
+            match &self.winning_holds {
+                Some((lo, hi)) => write!(f, "hold {}..={} ms to win ({} ways)", lo, hi, self.ways_to_win())?,
+                None => write!(f, "can't be won")?,
+            }
+            write!(f, ", best hold {} ms goes {} ({:+} on the record)", self.best_hold, self.best_distance, self.margin())?;
+            if !self.ties.is_empty() {
+                let ties: Vec<String> = self.ties.iter().map(|hold| hold.to_string()).collect();
+                write!(f, ", ties holding {} ms", ties.join(" or "))?;
+            }
+            Ok(())
+        }
+    }
+
+    impl Boat {
+        fn new(acceleration: BigInt, top_speed: Option<BigInt>) -> Self {
+            Boat {
+                acceleration,
+                top_speed,
+            }
+        }
+
+        fn standard() -> Self {
+            Boat::new(BigInt::one(), None)
+        }
+
+        fn distance(&self, hold: &BigInt, time: &BigInt) -> BigInt {
+            let speed = &self.acceleration * hold;
+            let speed = match &self.top_speed {
+                Some(top_speed) => speed.min(top_speed.clone()),
+                None => speed,
+            };
+            speed * (time - hold)
+        }
+
+        // the first hold time at which the boat is going at top speed
+        fn top_speed_hold(&self) -> Option<BigInt> {
+            self.top_speed.as_ref()
+                .filter(|_| self.acceleration.is_positive())
+                .map(|top_speed| top_speed.div_ceil(&self.acceleration))
+        }
+
+        fn winning_holds(&self, time: &BigInt, record: &BigInt) -> Option<(BigInt, BigInt)> {
+            if !self.acceleration.is_positive() || self.top_speed.as_ref().is_some_and(|s| !s.is_positive()) {
+                return None;
+            }
+            let capped_from = self.top_speed_hold();
+            let last_hold = match &capped_from {
+                Some(capped_from) => (capped_from - BigInt::one()).min(time.clone()),
+                None => time.clone(),
+            };
+
+            // while accelerating, a*t*(T - t) > D  =>  a*t^2 - a*T*t + D < 0
+            let accelerating = quadratic_negative_range(&self.acceleration, &(-&self.acceleration * time), record)
+                .map(|(lo, hi)| (lo.max(BigInt::zero()), hi.min(last_hold)))
+                .filter(|(lo, hi)| lo <= hi);
+
+            // at top speed s, s*(T - t) > D  =>  t < T - D/s
+            let at_top_speed = self.top_speed.as_ref()
+                .zip(capped_from)
+                .map(|(top_speed, capped_from)| (capped_from, time - record.div_floor(top_speed) - 1))
+                .filter(|(lo, hi)| lo <= hi);
+
+            // distance rises then falls, so the winning holds are contiguous
+            match (accelerating, at_top_speed) {
+                (Some((lo, _)), Some((_, hi))) => Some((lo, hi)),
+                (accelerating, at_top_speed) => accelerating.or(at_top_speed),
+            }
+        }
+
+        // hold times that exactly match the record while accelerating. a*t*(T - t) = D has
+        // integer roots only if the discriminant is a perfect square
+        fn ties(&self, time: &BigInt, record: &BigInt) -> Vec<BigInt> {
+            if !self.acceleration.is_positive() {
+                return vec![];
+            }
+            let a_t = &self.acceleration * time;
+            let Some(root) = exact_sqrt(&(&a_t * &a_t - BigInt::from(4) * &self.acceleration * record)) else {
+                return vec![];
+            };
+            let two_a: BigInt = &self.acceleration * 2;
+            let mut ties: Vec<BigInt> = [&a_t - &root, &a_t + &root].into_iter()
+                .filter(|n| n.is_multiple_of(&two_a))
+                .map(|n| n / &two_a)
+                .filter(|hold| !hold.is_negative() && hold <= time && self.distance(hold, time) == *record)
+                .collect();
+            ties.dedup();
+            ties
+        }
+
+        // the hold time that goes furthest, and how far it goes. distance peaks either at
+        // the middle of the race or where top speed is reached (or nowhere, for a boat that
+        // never moves)
+        fn best_hold(&self, time: &BigInt) -> (BigInt, BigInt) {
+            let half: BigInt = time / 2;
+            let mut candidates = vec![BigInt::zero(), half.clone(), half + 1];
+            if let Some(capped_from) = self.top_speed_hold() {
+                candidates.push(&capped_from - BigInt::one());
+                candidates.push(capped_from);
+            }
+            candidates.into_iter()
+                .map(|hold| hold.max(BigInt::zero()).min(time.clone()))
+                .map(|hold| {
+                    let distance = self.distance(&hold, time);
+                    (hold, distance)
+                })
+                .min_by(|(h1, d1), (h2, d2)| d2.cmp(d1).then(h1.cmp(h2)))
+                .unwrap()
+        }
+
+        fn analyse(&self, time: &BigInt, record: &BigInt) -> RaceAnalysis {
+            let (best_hold, best_distance) = self.best_hold(time);
+            RaceAnalysis {
+                time: time.clone(),
+                record: record.clone(),
+                winning_holds: self.winning_holds(time, record),
+                best_hold,
+                best_distance,
+                ties: self.ties(time, record),
+            }
+        }
+    }
+
+    impl Input {
+        fn races(&self) -> Vec<(BigInt, BigInt)> {
+            self.input.iter()
+                .map(|(time, record)| (time.parse().unwrap(), record.parse().unwrap()))
+                .collect()
+        }
+
+        // the single race you get by ignoring the spaces between the numbers
+        fn combined_race(&self) -> (BigInt, BigInt) {
+            let time: String = self.input.iter().map(|r| &*r.0).collect();
+            let record: String = self.input.iter().map(|r| &*r.1).collect();
+            (time.parse().unwrap(), record.parse().unwrap())
+        }
+
+        fn analyse(&self, boat: &Boat) -> Vec<RaceAnalysis> {
+            self.races().iter()
+                .map(|(time, record)| boat.analyse(time, record))
+                .collect()
+        }
+    }
+
     impl Solution for Input {
         fn part_1(&self) -> Box<dyn Display> {
-            let answer: i32 = self.input.iter().map(|r| {
-                let time = r.0.parse().unwrap();
-                let record = r.1.parse().unwrap();
-                (1..time)
-                .filter(|t| t * (time - t) > record)
-                    .count() as i32
-            }).product();
+            let answer: BigInt = self.analyse(&Boat::standard()).iter()
+                .map(RaceAnalysis::ways_to_win)
+                .product();
 
             Box::new(answer)
         }
 
         fn part_2(&self) -> Box<dyn Display> {
-            let time: String = self.input.iter().map(|r| &*r.0).collect();
-            let record: String = self.input.iter().map(|r| &*r.1).collect();
-            let time: BigInt = time.parse().unwrap();
-            let record: BigInt = record.parse().unwrap();
-
-            println!("({} + ({}*{} - 4 * {})^0.5) / 2", &time, &time, &time, &record);
-            println!("({} - ({}*{} - 4 * {})^0.5) / 2", &time, &time, &time, &record);
-            let answer = "put the above into Spotlight and count the numbers between them";
-            // 31,224,779.9109468335
-            // 7,723,190.08905316653
+            let (time, record) = self.combined_race();
+            let answer = Boat::standard().analyse(&time, &record).ways_to_win();
+
             Box::new(answer)
         }
+
+        fn report(&self) -> Option<String> {
+            let boat = Boat::standard();
+            let mut report = String::new();
+            for (i, analysis) in self.analyse(&boat).iter().enumerate() {
+                writeln!(report, "race {}: {}", i + 1, analysis).unwrap();
+            }
+            let (time, record) = self.combined_race();
+            writeln!(report, "combined: {}", boat.analyse(&time, &record)).unwrap();
+            Some(report)
+        }
     }
 }
 
 mod day_07 {
-    use std::cmp::Ordering;
     use std::collections::HashMap;
-    use std::fmt::Display;
+    use std::fmt::{Display, Formatter, Write};
     use crate::day::{Day, Solution};
 
     pub struct Day07;
@@ -631,137 +1789,239 @@ mod day_07 {
         }
     }
 
-    fn part_1_card_value(c: char) -> u8 {
-        match c {
-            'A' => 14,
-            'K' => 13,
-            'Q' => 12,
-            'J' => 11,
-            'T' => 10,
-            _   => c as u8 - '0' as u8
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+    enum Category {
+        HighCard,
+        OnePair,
+        TwoPair,
+        ThreeOfAKind,
+        FullHouse,
+        FourOfAKind,
+        // with more than five cards, any bigger group counts as five of a kind too
+        FiveOfAKind,
+    }
+
+    impl Category {
+        // from the sizes of the biggest and second-biggest groups of matching cards
+        fn from_groups(largest: usize, second: usize) -> Self {
+            match (largest, second) {
+                (5.., _) => Category::FiveOfAKind,
+                (4, _) => Category::FourOfAKind,
+                (3, 2..) => Category::FullHouse,
+                (3, _) => Category::ThreeOfAKind,
+                (2, 2..) => Category::TwoPair,
+                (2, _) => Category::OnePair,
+                _ => Category::HighCard,
+            }
         }
     }
 
-    fn part_1_hand_strength(hand: &[u8]) -> i32 {
-        let mut counts: Vec<i32> = hand.into_iter()
-            .fold(HashMap::new(), |mut m, c| {
-                *m.entry(*c).or_insert(0i32) += 1;
-                m
-            })
-            .values()
-            .copied()
-            .collect();
-        counts.sort();
-        match &*counts {
-            [5] => 7,
-            [1, 4] => 6,
-            [2, 3] => 5,
-            [1, 1, 3] => 4,
-            [1, 2, 2] => 3,
-            [1, 1, 1, 2] => 2,
-            [1, 1, 1, 1, 1] => 1,
-            _ => unreachable!()
-        }
-    }
-
-    fn part_2_card_value(c: char) -> u8 {
-        match c {
-            'A' => 14,
-            'K' => 13,
-            'Q' => 12,
-            'J' => 1,
-            'T' => 10,
-            _   => c as u8 - '0' as u8
-        }
-    }
-
-    fn part_2_hand_strength(hand: &[u8]) -> i32 {
-        let mut counts_without_jokers: Vec<i32> = hand.into_iter()
-            .filter(|v| **v != 1)
-            .fold(HashMap::new(), |mut m, c| {
-                *m.entry(*c).or_insert(0i32) += 1;
-                m
+    impl Display for Category {
+        fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+            f.write_str(match self {
+                Category::HighCard => "high card",
+                Category::OnePair => "one pair",
+                Category::TwoPair => "two pair",
+                Category::ThreeOfAKind => "three of a kind",
+                Category::FullHouse => "full house",
+                Category::FourOfAKind => "four of a kind",
+                Category::FiveOfAKind => "five of a kind",
             })
-            .values()
-            .copied()
-            .collect();
-        counts_without_jokers.sort();
-        match &*counts_without_jokers {
-            // no jokers
-            [5] => 7,
-            [1, 4] => 6,
-            [2, 3] => 5,
-            [1, 1, 3] => 4,
-            [1, 2, 2] => 3,
-            [1, 1, 1, 2] => 2,
-            [1, 1, 1, 1, 1] => 1,
-            // 1 joker
-            [4] => 7,
-            [1, 3] => 6,
-            [2, 2] => 5,
-            [1, 1, 2] => 4,
-            [1, 1, 1, 1] => 2,
-            // 2 joker
-            [3] => 7,
-            [1, 2] => 6,
-            [1, 1, 1] => 4,
-            // 3 joker
-            [2] => 7,
-            [1, 1] => 6,
-            // 4 joker
-            [1] => 7,
-            // 5 joker
-            [] => 7,
-            _ => unreachable!()
-        }
-    }
-
-    fn cmp_hands(hand1: &(i32, Vec<u8>, i32), hand2: &(i32, Vec<u8>, i32)) -> Ordering {
-        hand1.0.cmp(&hand2.0)
-            .then_with(|| hand1.1.iter().zip(hand2.1.iter())
-                .map(|(c1, c2)| c1.cmp(c2))
-                .fold(Ordering::Equal, |a, b| a.then(b))
-            )
+        }
     }
 
-    impl Solution for Input {
-        fn part_1(&self) -> Box<dyn Display> {
-            let mut hands: Vec<(i32, Vec<u8>, i32)> = self.input.iter()
-                .map(|(hand, bid)| {
-                    let hand: Vec<u8> = hand.iter().map(|c| part_1_card_value(*c)).collect();
-                    (part_1_hand_strength(&*hand), hand, *bid)
-                })
-                .collect();
+    // how a hand is scored: by category first, then card by card
+    #[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+    struct Evaluation {
+        category: Category,
+        card_values: Vec<u8>,
+    }
 
-            hands.sort_by(|h1, h2| cmp_hands(h1, h2));
+    struct Rules {
+        hand_size: usize,
+        // cards from weakest to strongest
+        ranking: Vec<char>,
+        // cards that stand in for whatever makes the hand strongest
+        wildcards: Vec<char>,
+        wildcard_name: &'static str,
+    }
 
-            let answer: i32 = hands.iter().zip(1..).map(|(h, r)| h.2 * r).sum();
+    impl Rules {
+        fn standard() -> Self {
+            Rules {
+                hand_size: 5,
+                ranking: "23456789TJQKA".chars().collect(),
+                wildcards: vec![],
+                wildcard_name: "wildcard",
+            }
+        }
 
-            Box::new(answer)
+        fn jokers() -> Self {
+            Rules {
+                hand_size: 5,
+                ranking: "J23456789TQKA".chars().collect(),
+                wildcards: vec!['J'],
+                wildcard_name: "joker",
+            }
         }
 
-        fn part_2(&self) -> Box<dyn Display> {
-            let mut hands: Vec<(i32, Vec<u8>, i32)> = self.input.iter()
-                .map(|(hand, bid)| {
-                    let hand: Vec<u8> = hand.iter().map(|c| part_2_card_value(*c)).collect();
-                    (part_2_hand_strength(&*hand), hand, *bid)
-                })
+        fn card_value(&self, c: char) -> u8 {
+            self.ranking.iter()
+                .position(|r| *r == c)
+                .unwrap_or_else(|| panic!("unknown card: {}", c)) as u8
+        }
+
+        fn category(&self, hand: &[char]) -> Category {
+            let mut wildcards = 0;
+            let mut counts: HashMap<char, usize> = HashMap::new();
+            for c in hand {
+                if self.wildcards.contains(c) {
+                    wildcards += 1;
+                } else {
+                    *counts.entry(*c).or_insert(0) += 1;
+                }
+            }
+
+            let mut counts: Vec<usize> = counts.into_values().collect();
+            counts.sort_unstable_by(|a, b| b.cmp(a));
+            let largest = counts.first().copied().unwrap_or(0);
+            let second = counts.get(1).copied().unwrap_or(0);
+
+            // wildcards always do best joining the biggest group
+            Category::from_groups(largest + wildcards, second)
+        }
+
+        fn evaluate(&self, hand: &[char]) -> Evaluation {
+            assert_eq!(hand.len(), self.hand_size, "wrong number of cards: {}", hand.iter().collect::<String>());
+            Evaluation {
+                category: self.category(hand),
+                card_values: hand.iter().map(|c| self.card_value(*c)).collect(),
+            }
+        }
+    }
+
+    // why a hand ranks above the one just below it
+    #[derive(Debug)]
+    enum TieBreak {
+        // the lowest-ranked hand
+        Bottom,
+        // a better category than the hand below
+        Category(Category),
+        // the same category, won at the first differing card
+        Card { index: usize, ours: char, theirs: char },
+        // identical cards, so ranked by input order
+        Tied,
+    }
+
+    impl Display for TieBreak {
+        fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+            match self {
+                TieBreak::Bottom => write!(f, "lowest hand"),
+                TieBreak::Category(category) => write!(f, "beats {}", category),
+                TieBreak::Card { index, ours, theirs } => write!(f, "card {}: {} over {}", index + 1, ours, theirs),
+                TieBreak::Tied => write!(f, "tied"),
+            }
+        }
+    }
+
+    struct RankedHand {
+        hand: String,
+        evaluation: Evaluation,
+        // how many of the hand's cards are wildcards
+        wildcards: usize,
+        tie_break: TieBreak,
+        rank: i32,
+        bid: i32,
+    }
+
+    impl RankedHand {
+        fn winnings(&self) -> i32 {
+            self.rank * self.bid
+        }
+    }
+
+    impl Input {
+        fn ranked(&self, rules: &Rules) -> Vec<RankedHand> {
+            let mut hands: Vec<(&Vec<char>, Evaluation, i32)> = self.input.iter()
+                .map(|(hand, bid)| (hand, rules.evaluate(hand), *bid))
                 .collect();
 
-            hands.sort_by(|h1, h2| cmp_hands(h1, h2));
+            hands.sort_by(|h1, h2| h1.1.cmp(&h2.1));
+
+            let tie_breaks = std::iter::once(TieBreak::Bottom)
+                .chain(hands.windows(2).map(|w| {
+                    let ((below, below_evaluation, _), (hand, evaluation, _)) = (&w[0], &w[1]);
+                    if evaluation.category != below_evaluation.category {
+                        return TieBreak::Category(below_evaluation.category);
+                    }
+                    match (0..hand.len()).find(|i| evaluation.card_values[*i] != below_evaluation.card_values[*i]) {
+                        Some(index) => TieBreak::Card { index, ours: hand[index], theirs: below[index] },
+                        None => TieBreak::Tied,
+                    }
+                }))
+                .collect::<Vec<_>>();
+
+            hands.into_iter()
+                .zip(tie_breaks)
+                .zip(1..)
+                .map(|(((hand, evaluation, bid), tie_break), rank)| RankedHand {
+                    hand: hand.iter().collect(),
+                    evaluation,
+                    wildcards: hand.iter().filter(|c| rules.wildcards.contains(c)).count(),
+                    tie_break,
+                    rank,
+                    bid,
+                })
+                .collect()
+        }
+
+        fn winnings(&self, rules: &Rules) -> i32 {
+            self.ranked(rules).iter().map(RankedHand::winnings).sum()
+        }
+
+        // every hand from lowest to highest, with how it was ranked
+        fn table(&self, rules: &Rules) -> String {
+            let mut report = String::new();
+            let hand_width = rules.hand_size.max(4) + 2;
+            writeln!(report, "{:>5}  {:<hand_width$}{:<32}{:<24}{:>6}{:>10}", "rank", "hand", "category", "tie-break", "bid", "winnings").unwrap();
+            for ranked in self.ranked(rules) {
+                let category = match ranked.wildcards {
+                    0 => ranked.evaluation.category.to_string(),
+                    1 => format!("{} via 1 {}", ranked.evaluation.category, rules.wildcard_name),
+                    n => format!("{} via {} {}s", ranked.evaluation.category, n, rules.wildcard_name),
+                };
+                writeln!(report, "{:>5}  {:<hand_width$}{:<32}{:<24}{:>6}{:>10}",
+                    ranked.rank, ranked.hand, category, ranked.tie_break.to_string(), ranked.bid, ranked.winnings()).unwrap();
+            }
+            report
+        }
+    }
+
+    impl Solution for Input {
+        fn part_1(&self) -> Box<dyn Display> {
+            let answer = self.winnings(&Rules::standard());
 
-            let answer: i32 = hands.iter().zip(1..).map(|(h, r)| h.2 * r).sum();
+            Box::new(answer)
+        }
+
+        fn part_2(&self) -> Box<dyn Display> {
+            let answer = self.winnings(&Rules::jokers());
 
             Box::new(answer)
         }
+
+        fn report(&self) -> Option<String> {
+            Some(format!("standard:\n{}\njokers:\n{}", self.table(&Rules::standard()), self.table(&Rules::jokers())))
+        }
     }
 }
 
 mod day_08 {
-    use std::collections::HashMap;
-    use std::fmt::Display;
-    use regex::Regex;
+    use std::collections::{BTreeMap, HashMap};
+    use std::fmt::{Display, Write};
     use crate::day::{Day, Solution};
+    use crate::parse::parse;
+    use crate::math::{crt, gcd_all, lcm_all};
 
     #[derive(Debug)]
     enum Dir { L, R, }
@@ -785,242 +2045,486 @@ mod day_08 {
     struct Input {
         movements: Vec<Dir>,
         graph: HashMap<i32, (i32, i32)>,
+        symbols: Symbols,
     }
 
-    fn to_id(name: &str) -> i32 {
-        let bytes = name.as_bytes();
-        (bytes[0] as i32 - 'A' as i32) * 26 * 26 +
-            (bytes[1] as i32 - 'A' as i32) * 26 +
-            (bytes[2] as i32 - 'A' as i32)
+    // node names, numbered in the order they're first seen
+    #[derive(Default)]
+    struct Symbols {
+        names: Vec<String>,
+        ids: HashMap<String, i32>,
     }
 
-    #[allow(unused)]
-    fn to_name(id: i32) -> String {
-        let mut s = String::new();
-        s.push((id / 26 / 26 + 'A' as i32) as u8 as char);
-        s.push(((id / 26) % 26 + 'A' as i32) as u8 as char);
-        s.push((id % 26 + 'A' as i32) as u8 as char);
-        s
+    impl Symbols {
+        fn intern(&mut self, name: &str) -> i32 {
+            if let Some(id) = self.ids.get(name) {
+                return *id;
+            }
+            let id = self.names.len() as i32;
+            self.names.push(name.to_owned());
+            self.ids.insert(name.to_owned(), id);
+            id
+        }
+
+        fn id(&self, name: &str) -> Option<i32> {
+            self.ids.get(name).copied()
+        }
+
+        fn name(&self, id: i32) -> &str {
+            &self.names[id as usize]
+        }
     }
 
     impl Day for Day08 {
         fn process_input(&self, input: &str) -> Box<dyn Solution> {
-            let mut lines = input.lines();
+            let mut symbols = Symbols::default();
+
+            let (movements, graph) = parse(input, |p| {
+                let movements = p.line(|p| Ok(p.take_while(|c| c == 'L' || c == 'R').chars().map(Dir::from).collect()))?;
+                p.tag("\n")?;
+
+                let graph = p.lines(|p| {
+                    let node = symbols.intern(p.word()?);
+                    p.tag(" = (")?;
+                    let left = symbols.intern(p.word()?);
+                    p.tag(", ")?;
+                    let right = symbols.intern(p.word()?);
+                    p.tag(")")?;
+                    Ok((node, (left, right)))
+                })?;
+
+                Ok((movements, graph.into_iter().collect()))
+            });
+
+            Box::new(Input {
+                movements,
+                graph,
+                symbols,
+            })
+        }
+    }
+
+    impl Input {
+        fn is_start(&self, id: i32) -> bool {
+            self.symbols.name(id).ends_with('A')
+        }
+
+        fn is_end(&self, id: i32) -> bool {
+            self.symbols.name(id).ends_with('Z')
+        }
+
+        fn ghosts(&self) -> Vec<Ghost> {
+            (0..self.symbols.names.len() as i32)
+                .filter(|id| self.graph.contains_key(id) && self.is_start(*id))
+                .map(|id| self.ghost(id))
+                .collect()
+        }
+
+        // steps from one node to another, or None if the walk loops without getting there
+        fn steps(&self, from: i32, to: i32) -> Option<u64> {
+            let mut seen = vec![false; self.symbols.names.len() * self.movements.len()];
+            let mut loc = from;
+            let mut step = 0u64;
+
+            loop {
+                let instruction = step as usize % self.movements.len();
+                let state = loc as usize * self.movements.len() + instruction;
+                if seen[state] {
+                    return None;
+                }
+                seen[state] = true;
+                loc = self.movements[instruction].go(*self.graph.get(&loc).unwrap());
+                step += 1;
+                if loc == to {
+                    return Some(step);
+                }
+            }
+        }
 
-            let movements = lines.next().unwrap().chars().map(Dir::from).collect();
+        // walks until a (node, instruction) state repeats, recording the steps that land on an end node
+        fn ghost(&self, start: i32) -> Ghost {
+            let mut first_seen: Vec<Option<u64>> = vec![None; self.symbols.names.len() * self.movements.len()];
+            let mut hits = vec![];
+            let mut loc = start;
+            let mut step = 0u64;
+
+            loop {
+                let instruction = step as usize % self.movements.len();
+                let state = loc as usize * self.movements.len() + instruction;
+                if let Some(cycle_start) = first_seen[state] {
+                    let (lead_in, cycle) = hits.iter().partition(|t| **t < cycle_start);
+                    return Ghost {
+                        start,
+                        lead_in,
+                        cycle_start,
+                        period: step - cycle_start,
+                        cycle,
+                    };
+                }
+                first_seen[state] = Some(step);
+                if self.is_end(loc) {
+                    hits.push(step);
+                }
+                loc = self.movements[instruction].go(*self.graph.get(&loc).unwrap());
+                step += 1;
+            }
+        }
 
-            lines.next();
+        // the first step at which every ghost stands on an end node, if there is one
+        fn align(&self, ghosts: &[Ghost]) -> Option<u64> {
+            // before every ghost is in its cycle, some ghost is still in its lead-in
+            let latest = ghosts.iter().max_by_key(|g| g.cycle_start)?;
+            if let Some(t) = latest.lead_in.iter().find(|t| ghosts.iter().all(|g| g.hits(**t))) {
+                return Some(*t);
+            }
 
-            let line_regex = Regex::new("^(...) = \\((...), (...)\\)$").unwrap();
+            // afterwards, each ghost hits whenever t = some cycle hit (mod its period)
+            let mut choices: Vec<Vec<(i128, i128)>> = vec![vec![]];
+            for ghost in ghosts {
+                choices = choices.into_iter()
+                    .flat_map(|chosen| ghost.cycle.iter().map(move |t| {
+                        let mut chosen = chosen.clone();
+                        chosen.push((*t as i128, ghost.period as i128));
+                        chosen
+                    }))
+                    .collect();
+            }
 
-            let graph = lines
-                .map(|line| {
-                    let captures = line_regex.captures(line).unwrap();
-                    (
-                        to_id(captures.get(1).unwrap().as_str()),
-                        (
-                            to_id(captures.get(2).unwrap().as_str()),
-                            to_id(captures.get(3).unwrap().as_str())
-                        )
-                    )
-                })
-                .collect();
+            let from = latest.cycle_start as i128;
+            choices.iter()
+                .filter_map(|congruences| crt(congruences))
+                .map(|(offset, modulus)| offset + (from - offset + modulus - 1) / modulus * modulus)
+                .min()
+                .map(|t| t as u64)
+        }
+
+        // graphviz source for the network. starts are green and ends red; each ghost's walk is
+        // drawn in its own colour, dashed through the lead-in and bold around the cycle
+        fn dot(&self) -> String {
+            const COLOURS: [&str; 8] = ["blue", "orange", "purple", "brown", "cyan3", "magenta", "gold3", "darkgreen"];
+
+            let ghosts = self.ghosts();
+
+            // (from, direction) -> (ghost, in cycle) for every ghost that takes that edge
+            let mut walked: BTreeMap<(i32, usize), Vec<(usize, bool)>> = BTreeMap::new();
+            for (g, ghost) in ghosts.iter().enumerate() {
+                let mut loc = ghost.start;
+                for step in 0..ghost.cycle_start + ghost.period {
+                    let instruction = step as usize % self.movements.len();
+                    let dir = match self.movements[instruction] { Dir::L => 0, Dir::R => 1 };
+                    let in_cycle = step >= ghost.cycle_start;
+                    let on_edge = walked.entry((loc, dir)).or_default();
+                    match on_edge.iter_mut().find(|(other, _)| *other == g) {
+                        Some((_, cycle)) => *cycle |= in_cycle,
+                        None => on_edge.push((g, in_cycle)),
+                    }
+                    loc = self.movements[instruction].go(*self.graph.get(&loc).unwrap());
+                }
+            }
 
-            Box::new(Input {
-                movements,
-                graph,
-            })
+            let mut dot = String::new();
+            writeln!(dot, "digraph network {{").unwrap();
+            let periods: Vec<u64> = ghosts.iter().map(|ghost| ghost.period).collect();
+            writeln!(dot, "    // cycles share a factor of {}; together they repeat every {} steps",
+                gcd_all(periods.iter().copied()), lcm_all(periods.iter().copied())).unwrap();
+            for ghost in &ghosts {
+                writeln!(dot, "    // {}: {} step lead-in, {} step cycle, ends on steps {:?} in the lead-in and {:?} in the cycle",
+                    self.symbols.name(ghost.start), ghost.cycle_start, ghost.period, ghost.lead_in, ghost.cycle).unwrap();
+            }
+            for id in 0..self.symbols.names.len() as i32 {
+                let name = self.symbols.name(id);
+                if self.is_start(id) {
+                    writeln!(dot, "    \"{}\" [style=filled, fillcolor=palegreen];", name).unwrap();
+                } else if self.is_end(id) {
+                    writeln!(dot, "    \"{}\" [style=filled, fillcolor=lightpink];", name).unwrap();
+                }
+            }
+            for id in 0..self.symbols.names.len() as i32 {
+                let Some(&(left, right)) = self.graph.get(&id) else { continue };
+                for (dir, label, to) in [(0, "L", left), (1, "R", right)] {
+                    write!(dot, "    \"{}\" -> \"{}\" [label={}", self.symbols.name(id), self.symbols.name(to), label).unwrap();
+                    if let Some(ghosts) = walked.get(&(id, dir)) {
+                        let colours: Vec<&str> = ghosts.iter().map(|(g, _)| COLOURS[g % COLOURS.len()]).collect();
+                        let style = if ghosts.iter().any(|(_, cycle)| *cycle) { "bold" } else { "dashed" };
+                        write!(dot, ", color=\"{}\", style={}", colours.join(":"), style).unwrap();
+                    }
+                    writeln!(dot, "];").unwrap();
+                }
+            }
+            writeln!(dot, "}}").unwrap();
+            dot
         }
     }
 
-    impl Input {
-        fn period(&self, start: i32) -> (Vec<u64>, u64, Vec<u64>) {
-            let mut zs: Vec<(u64, i32)> = vec![];
-            let mut loc = start;
-            let mut moves = 0u64;
-            for dir in self.movements.iter().cycle() {
-                loc = dir.go(*self.graph.get(&loc).unwrap());
-                moves += 1;
-                if loc % 26 == 25 {
-                    if let Some((period_start, _)) = zs.iter().filter(|z| z.1 == loc).next() {
-                        let period_end = moves;
-                        return (
-                            zs.iter().copied()
-                                .take_while(|(p, _)| p < period_start)
-                                .map(|(p, _)| p)
-                                .collect(),
-                            period_end - period_start,
-                            zs.iter().copied()
-                                .skip_while(|(p, _)| p < period_start)
-                                .map(|(p, _)| p)
-                                .collect(),
-                        );
-                    } else {
-                        zs.push((moves, loc));
-                    }
-                }
+    // the steps at which a ghost stands on an end node: each lead-in hit once, then each
+    // cycle hit again every period
+    struct Ghost {
+        start: i32,
+        lead_in: Vec<u64>,
+        cycle_start: u64,
+        period: u64,
+        cycle: Vec<u64>,
+    }
+
+    impl Ghost {
+        fn hits(&self, t: u64) -> bool {
+            if t < self.cycle_start {
+                self.lead_in.contains(&t)
+            } else {
+                self.cycle.contains(&(self.cycle_start + (t - self.cycle_start) % self.period))
             }
-            unreachable!()
         }
     }
 
     impl Solution for Input {
         fn part_1(&self) -> Box<dyn Display> {
-            let mut moves = 0;
-            let mut loc = to_id("AAA");
-
-            for dir in self.movements.iter().cycle() {
-                loc = dir.go(*self.graph.get(&loc).unwrap());
-                moves += 1;
-                if loc == to_id("ZZZ") {
-                    break;
-                }
-            }
-
-            let answer: i32 = moves;
+            let (Some(start), Some(end)) = (self.symbols.id("AAA"), self.symbols.id("ZZZ")) else {
+                return Box::new("no AAA/ZZZ in this network");
+            };
 
-            Box::new(answer)
+            match self.steps(start, end) {
+                Some(answer) => Box::new(answer),
+                None => Box::new("ZZZ can't be reached from AAA"),
+            }
         }
 
         fn part_2(&self) -> Box<dyn Display> {
-            let starters: Vec<i32> = self.graph.keys().copied().filter(|id| *id % 26 == 0).collect();
-
-            // all paths are periodic through a single Z node, with periods equal
-            // to the distance from the respective A node. This means we can just
-            // lowest-common-multiple the periods. This solution is more general,
-            // working for multi-end cycles and differently-sized/non-cyclical
-            // lead-in sequences.
-
-            let periods: Vec<(Vec<u64>, u64, Vec<u64>)> = starters.into_iter()
-                .map(|id| self.period(id))
-                .collect();
-
-            let answer = (0..)
-                .flat_map(|n| {
-                    let n = n;
-                    let period = periods[0].1;
-                    periods[0].2.iter()
-                        .map(move |x| n * period + x)
-                })
-                .filter(|x| {
-                    periods[1..].iter().all(|ghost| {
-                        ghost.0.contains(x) || ghost.2.iter().any(|y| (x - y) % ghost.1 == 0)
-                    })
-                })
-                .next().unwrap();
+            match self.align(&self.ghosts()) {
+                Some(answer) => Box::new(answer),
+                None => Box::new("ghosts never align"),
+            }
+        }
 
-            Box::new(answer)
+        fn report(&self) -> Option<String> {
+            Some(self.dot())
         }
     }
 }
 
 mod day_09 {
-    use std::fmt::Display;
+    use std::fmt::{Display, Formatter, Write};
+    use num::{BigInt, BigRational, One, Signed, Zero};
     use crate::day::{Day, Solution};
 
     pub struct Day09;
     struct Input {
-        diff_seqss: Vec<Vec<Vec<i32>>>,
+        sequences: Vec<Vec<i32>>,
+        polynomials: Vec<Polynomial>,
+    }
+
+    // the lowest-degree polynomial through a sequence sampled at 0, 1, 2, ..., kept in newton
+    // form: the first entry of each row of the difference table
+    struct Polynomial {
+        differences: Vec<BigInt>,
+        samples: usize,
+    }
+
+    impl Polynomial {
+        fn fit(sequence: &[i32]) -> Self {
+            let rows = std::iter::successors(
+                Some(sequence.iter().map(|n| BigInt::from(*n)).collect::<Vec<BigInt>>()),
+                |seq| Some(std::iter::zip(seq.iter(), seq.iter().skip(1)).map(|(a, b)| b - a).collect()),
+            );
+
+            Polynomial {
+                differences: rows
+                    .take_while(|seq| !seq.iter().all(|n| n.is_zero()))
+                    .map(|seq| seq[0].clone())
+                    .collect(),
+                samples: sequence.len(),
+            }
+        }
+
+        // the zero polynomial counts as degree 0
+        fn degree(&self) -> usize {
+            self.differences.len().saturating_sub(1)
+        }
+
+        // the difference table never reached a row of zeros, so any continuation fits
+        // the samples equally well
+        fn is_underdetermined(&self) -> bool {
+            self.degree() + 1 >= self.samples
+        }
+
+        // sum of differences[j] * (k choose j), which also holds for negative k
+        fn at(&self, k: i64) -> BigInt {
+            let k = BigInt::from(k);
+            let mut binomial = BigInt::one();
+            let mut value = BigInt::zero();
+            for (j, difference) in self.differences.iter().enumerate() {
+                value += difference * &binomial;
+                binomial = binomial * (&k - j) / (j + 1);
+            }
+            value
+        }
+
+        // ordinary coefficients, constant term first
+        fn coefficients(&self) -> Vec<BigRational> {
+            let mut coefficients = vec![BigRational::zero(); self.differences.len().max(1)];
+            // k (k - 1) ... (k - j + 1) / j!
+            let mut basis = vec![BigRational::one()];
+            for (j, difference) in self.differences.iter().enumerate() {
+                for (c, b) in coefficients.iter_mut().zip(basis.iter()) {
+                    *c += b * difference;
+                }
+                let scale = BigRational::from(BigInt::from(j + 1));
+                let shift = BigRational::from(BigInt::from(j));
+                let mut next = vec![BigRational::zero(); basis.len() + 1];
+                for (i, b) in basis.iter().enumerate() {
+                    next[i + 1] += b / &scale;
+                    next[i] -= b * &shift / &scale;
+                }
+                basis = next;
+            }
+            coefficients
+        }
+    }
+
+    // in terms of the index k, highest power first
+    impl Display for Polynomial {
+        fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+            let mut written = false;
+            for (power, c) in self.coefficients().iter().enumerate().rev().filter(|(_, c)| !c.is_zero()) {
+                let variable = match power {
+                    0 => String::new(),
+                    1 => "k".to_owned(),
+                    _ => format!("k^{}", power),
+                };
+                // whole numbers are written bare, with their sign as the joining operator
+                let coefficient = if !c.is_integer() {
+                    if written { write!(f, " + ")?; }
+                    format!("({})", c)
+                } else {
+                    match (written, c.is_negative()) {
+                        (true, true) => write!(f, " - ")?,
+                        (true, false) => write!(f, " + ")?,
+                        (false, true) => write!(f, "-")?,
+                        (false, false) => (),
+                    }
+                    let magnitude = c.abs();
+                    if magnitude.is_one() && power > 0 { String::new() } else { magnitude.to_string() }
+                };
+                write!(f, "{}{}", coefficient, variable)?;
+                written = true;
+            }
+            if !written {
+                write!(f, "0")?;
+            }
+            Ok(())
+        }
     }
 
     impl Day for Day09 {
         fn process_input(&self, input: &str) -> Box<dyn Solution> {
-            let input: Vec<Vec<i32>> = input.lines()
+            let sequences: Vec<Vec<i32>> = input.lines()
                 .map(|s| s.split(' ').map(|s| s.parse().unwrap()).collect())
                 .collect();
 
-            let diff_seqss: Vec<Vec<Vec<i32>>> = input.iter()
-                .map(|sequence|
-                     std::iter::successors(
-                        Some((*sequence).clone()),
-                        |seq| {
-                            let diff_seq: Vec<i32> = std::iter::zip(seq.iter(), seq.iter().skip(1))
-                                .map(|(a, b)| b - a)
-                                .collect();
-                            if diff_seq.iter().all(|n| *n == 0) {
-                                None
-                            } else {
-                                Some(diff_seq)
-                            }
-                        }
-                    ).collect()
-                ).collect();
+            let polynomials = sequences.iter()
+                .map(|sequence| Polynomial::fit(sequence))
+                .collect();
 
             Box::new(Input {
-                diff_seqss,
+                sequences,
+                polynomials,
             })
         }
     }
 
+    impl Input {
+        // line numbers of sequences too short to pin down their polynomial
+        fn underdetermined(&self) -> Vec<usize> {
+            self.polynomials.iter().enumerate()
+                .filter(|(_, polynomial)| polynomial.is_underdetermined())
+                .map(|(i, _)| i + 1)
+                .collect()
+        }
+
+        // the sum over all sequences of their value at index(length)
+        fn extrapolate(&self, index: impl Fn(usize) -> i64) -> BigInt {
+            std::iter::zip(self.sequences.iter(), self.polynomials.iter())
+                .map(|(sequence, polynomial)| polynomial.at(index(sequence.len())))
+                .sum()
+        }
+    }
+
     impl Solution for Input {
         fn part_1(&self) -> Box<dyn Display> {
-            let answer: i32 = self.diff_seqss.iter()
-                .map(|diff_seqs| {
-                    let last = diff_seqs.iter()
-                        .rev()
-                        .fold(0, |acc, seq| *seq.last().unwrap() + acc);
-                    last
-                })
-                .sum();
+            let answer = self.extrapolate(|len| len as i64);
 
             Box::new(answer)
         }
 
         fn part_2(&self) -> Box<dyn Display> {
-            let answer: i32 = self.diff_seqss.iter()
-            .map(|diff_seqs| {
-                let last = diff_seqs.iter()
-                    .rev()
-                    .fold(0, |acc, seq| *seq.first().unwrap() - acc);
-                last
-            })
-            .sum();
+            let answer = self.extrapolate(|_| -1);
 
             Box::new(answer)
         }
+
+        fn report(&self) -> Option<String> {
+            let mut report = String::new();
+            for (i, polynomial) in self.polynomials.iter().enumerate() {
+                write!(report, "line {}: degree {} from {} values, {}", i + 1, polynomial.degree(), polynomial.samples, polynomial).unwrap();
+                if polynomial.is_underdetermined() {
+                    write!(report, " (underdetermined)").unwrap();
+                }
+                writeln!(report).unwrap();
+            }
+            let underdetermined: Vec<String> = self.underdetermined().iter().map(|line| line.to_string()).collect();
+            if !underdetermined.is_empty() {
+                writeln!(report, "both parts guess at lines {}", underdetermined.join(", ")).unwrap();
+            }
+            Some(report)
+        }
     }
 }
 
 mod day_11 {
     use std::collections::{HashMap, HashSet};
-    use std::fmt::Display;
+    use std::fmt::{Display, Write};
     use crate::day::{Day, Solution};
 
     pub struct Day11;
     struct Input {
-        galaxy_coords: HashSet<(i32, i32)>,
+        galaxy_coords: Vec<(i32, i32)>,
     }
 
-    fn expand_gaps(coords: &HashSet<i32>, extra: i32) -> HashMap<i32, i32> {
-        let mut running_extra = 0;
-        let mut adjusted: HashMap<i32, i32> = HashMap::new();
-        for x in (*coords.iter().min().unwrap())..=*coords.iter().max().unwrap() {
-            if !coords.contains(&x) {
-                running_extra += extra;
-            }
-            adjusted.insert(x, x + running_extra);
-        }
-        adjusted
+    // galaxy positions after expansion, numbered from 1 in reading order
+    struct Universe {
+        galaxies: Vec<(i64, i64)>,
+    }
+
+    // each empty line becomes `factor` lines. the empty lines before an occupied one are
+    // its distance from the first occupied line less the occupied lines in between
+    fn expand_gaps(coords: &HashSet<i32>, factor: i64) -> HashMap<i32, i64> {
+        let mut sorted: Vec<i32> = coords.iter().copied().collect();
+        sorted.sort_unstable();
+        sorted.iter().enumerate()
+            .map(|(rank, x)| {
+                let gaps = (x - sorted[0]) as i64 - rank as i64;
+                (*x, *x as i64 + gaps * (factor - 1))
+            })
+            .collect()
     }
 
-    fn expand(coords: &HashSet<(i32, i32)>, extra: i32) -> Vec<(i32, i32)> {
+    fn expand(coords: &[(i32, i32)], factor: i64) -> Vec<(i64, i64)> {
         let xs: HashSet<i32> = coords.iter().map(|(x, _)| *x).collect();
         let ys: HashSet<i32> = coords.iter().map(|(_, y)| *y).collect();
 
-        let adjusted_xs = expand_gaps(&xs, extra);
-        let adjusted_ys = expand_gaps(&ys, extra);
+        let adjusted_xs = expand_gaps(&xs, factor);
+        let adjusted_ys = expand_gaps(&ys, factor);
 
-        coords.into_iter()
+        coords.iter()
             .map(|(x, y)| (
-                *adjusted_xs.get(&x).unwrap() as i32,
-                *adjusted_ys.get(&y).unwrap() as i32
+                *adjusted_xs.get(x).unwrap(),
+                *adjusted_ys.get(y).unwrap()
             ))
             .collect()
     }
 
     impl Day for Day11 {
         fn process_input(&self, input: &str) -> Box<dyn Solution> {
-            let galaxy_coords: HashSet<(i32, i32)> = input.lines().enumerate()
+            let galaxy_coords: Vec<(i32, i32)> = input.lines().enumerate()
                 .flat_map(|(y, line)| line.chars().enumerate().map(move |(x, c)| ((x as i32, y as i32), c)))
                 .filter_map(|(p, c)| if c == '#' { Some(p) } else { None })
                 .collect();
@@ -1031,41 +2535,119 @@ mod day_11 {
         }
     }
 
-    fn sum_distances(coords: &[(i32 ,i32)]) -> i64 {
-        let mut answer: i64 = 0;
+    // once sorted, each value is the far end of a pair with every value before it
+    fn sum_axis_distances(mut values: Vec<i64>) -> u128 {
+        values.sort_unstable();
+        let mut before: i128 = 0;
+        let mut total: i128 = 0;
+        for (i, v) in values.into_iter().enumerate() {
+            total += v as i128 * i as i128 - before;
+            before += v as i128;
+        }
+        total as u128
+    }
+
+    fn sum_distances(coords: &[(i64, i64)]) -> u128 {
+        sum_axis_distances(coords.iter().map(|(x, _)| *x).collect()) +
+            sum_axis_distances(coords.iter().map(|(_, y)| *y).collect())
+    }
 
-        for i in 0..coords.len()-1 {
-            for j in i+1..coords.len() {
-                answer += (coords[i].0 - coords[j].0).abs() as i64 +
-                    (coords[i].1 - coords[j].1).abs() as i64;
+    impl Input {
+        fn universe(&self, factor: i64) -> Universe {
+            Universe {
+                galaxies: expand(&self.galaxy_coords, factor),
             }
         }
+    }
+
+    impl Universe {
+        fn galaxy(&self, number: usize) -> Option<(i64, i64)> {
+            self.galaxies.get(number.checked_sub(1)?).copied()
+        }
+
+        fn distance(&self, a: usize, b: usize) -> Option<u64> {
+            let (a, b) = (self.galaxy(a)?, self.galaxy(b)?);
+            Some(a.0.abs_diff(b.0) + a.1.abs_diff(b.1))
+        }
+
+        // (number, distance) of every other galaxy
+        fn others(&self, number: usize) -> impl Iterator<Item=(usize, u64)> + '_ {
+            (1..=self.galaxies.len())
+                .filter(move |other| *other != number)
+                .filter_map(move |other| Some((other, self.distance(number, other)?)))
+        }
+
+        // ties go to the lower-numbered galaxy
+        fn nearest(&self, number: usize) -> Option<(usize, u64)> {
+            self.others(number).min_by_key(|(other, distance)| (*distance, *other))
+        }
+
+        fn farthest(&self, number: usize) -> Option<(usize, u64)> {
+            self.others(number).max_by_key(|(other, distance)| (*distance, std::cmp::Reverse(*other)))
+        }
+
+        fn sum_distances(&self) -> u128 {
+            sum_distances(&self.galaxies)
+        }
 
-        answer
+        // csv, with galaxy numbers along the top and down the side
+        fn matrix(&self) -> String {
+            let numbers = 1..=self.galaxies.len();
+            let mut matrix = String::new();
+            for number in numbers.clone() {
+                write!(matrix, ",{}", number).unwrap();
+            }
+            writeln!(matrix).unwrap();
+            for a in numbers.clone() {
+                write!(matrix, "{}", a).unwrap();
+                for b in numbers.clone() {
+                    write!(matrix, ",{}", self.distance(a, b).unwrap()).unwrap();
+                }
+                writeln!(matrix).unwrap();
+            }
+            matrix
+        }
     }
 
     impl Solution for Input {
         fn part_1(&self) -> Box<dyn Display> {
-            let expanded = expand(&self.galaxy_coords, 1);
-            let answer = sum_distances(&expanded);
+            let answer = self.universe(2).sum_distances();
 
             Box::new(answer)
         }
 
         fn part_2(&self) -> Box<dyn Display> {
-            let expanded = expand(&self.galaxy_coords, 999999);
-            let answer = sum_distances(&expanded);
+            let answer = self.universe(1_000_000).sum_distances();
 
             Box::new(answer)
         }
+
+        // for the part 1 universe. the full matrix is only worth printing for small ones
+        fn report(&self) -> Option<String> {
+            let universe = self.universe(2);
+            let count = universe.galaxies.len();
+            let mut report = String::new();
+            for number in 1..=count {
+                if let (Some(nearest), Some(farthest)) = (universe.nearest(number), universe.farthest(number)) {
+                    writeln!(report, "galaxy {} at {:?}: nearest {} ({} away), farthest {} ({} away)",
+                        number, universe.galaxy(number).unwrap(), nearest.0, nearest.1, farthest.0, farthest.1).unwrap();
+                }
+            }
+            if count > 1 {
+                writeln!(report, "galaxy 1 to galaxy {}: {}", count, universe.distance(1, count).unwrap()).unwrap();
+            }
+            if count <= 20 {
+                write!(report, "{}", universe.matrix()).unwrap();
+            }
+            Some(report)
+        }
     }
 }
 
 mod day_12 {
-    use std::collections::HashMap;
-    use std::fmt::Display;
+    use std::fmt::{Display, Write};
     use crate::day::{Day, Solution};
-    use crate::day_12::Progress::{Either, MustBeDamaged, MustBeOperational};
+    use crate::memo::{Memo, MemoStats};
     use crate::day_12::Spring::Unknown;
 
     #[derive(Debug, Eq, PartialEq, Clone, Copy)]
@@ -1084,6 +2666,15 @@ mod day_12 {
                 _   => panic!("invalid spring character: {}", c),
             }
         }
+
+        // what the spring could really be
+        fn options(self) -> &'static [Spring] {
+            match self {
+                Spring::Operational => &[Spring::Operational],
+                Spring::Damaged => &[Spring::Damaged],
+                Unknown => &[Spring::Damaged, Spring::Operational],
+            }
+        }
     }
 
     pub struct Day12;
@@ -1108,7 +2699,6 @@ mod day_12 {
         }
     }
 
-    #[allow(unused)]
     fn show(r: &[Spring]) -> String {
         r.iter().map(|s| match s {
             Spring::Operational => '.',
@@ -1117,76 +2707,167 @@ mod day_12 {
         }).collect()
     }
 
-    #[derive(Debug, Eq, PartialEq, Clone, Copy, Hash)]
-    enum Progress {
-        MustBeDamaged,
-        MustBeOperational,
-        Either,
+    // copies of the row joined by unknown springs, and the groups repeated to match
+    fn unfold(springs: &[Spring], groups: &[i32], factor: usize) -> (Vec<Spring>, Vec<i32>) {
+        (vec![springs; factor].join(&Unknown), groups.repeat(factor))
     }
 
-    fn variations_2(running: &mut Vec<Spring>, cache: &mut HashMap<(usize, Progress, Vec<i32>), u64>, index: usize, next: Progress, groups: &mut [i32]) -> u64 {
-        let g_clone = groups.to_owned();
-        if let Some(cached) = cache.get(&(index, next, g_clone)) {
-            return *cached;
+    // splitmix64; enough to pick arrangements without pulling in a crate
+    struct Rng(u64);
+
+    impl Rng {
+        fn new(seed: u64) -> Self {
+            Rng(seed)
         }
-        let g_clone = groups.to_owned();
 
-        // println!("{} {:?} {:?}", index, next, groups);
-        if index == running.len() {
-            if groups.is_empty() {
-                return 1;
-            } else {
-                return 0;
+        fn next(&mut self) -> u64 {
+            self.0 = self.0.wrapping_add(0x9e3779b97f4a7c15);
+            let mut z = self.0;
+            z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+            z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+            z ^ (z >> 31)
+        }
+
+        // uniform in 0..n, rejecting the top sliver that would bias the modulo
+        fn below(&mut self, n: u64) -> u64 {
+            let zone = u64::MAX - u64::MAX % n;
+            loop {
+                let x = self.next();
+                if x < zone {
+                    return x % n;
+                }
             }
         }
+    }
 
-        let v = match (running[index], next) {
-            (Spring::Damaged, MustBeDamaged | Either) =>
-                if groups[0] == 1 {
-                    // end of group
-                    variations_2(running, cache, index + 1, MustBeOperational, &mut groups[1..])
-                } else {
-                    // group still ongoing
-                    groups[0] -= 1;
-                    let v = variations_2(running, cache, index + 1, MustBeDamaged, groups);
-                    groups[0] += 1;
-                    v
-                },
-            (Spring::Damaged, MustBeOperational) => 0,
-            (Spring::Operational, MustBeDamaged) => 0,
-            (Spring::Operational, MustBeOperational | Either) =>
-                variations_2(running, cache, index + 1, if groups.is_empty() { MustBeOperational } else { Either }, groups),
-            (Spring::Unknown, MustBeDamaged) =>
-                if groups[0] == 1 {
-                    // end of group
-                    variations_2(running, cache, index + 1, MustBeOperational, &mut groups[1..])
-                } else {
-                    // group still ongoing
-                    groups[0] -= 1;
-                    let v = variations_2(running, cache, index + 1, MustBeDamaged, groups);
-                    groups[0] += 1;
-                    v
-                },
-            (Spring::Unknown, MustBeOperational) =>
-                variations_2(running, cache, index + 1, if groups.is_empty() { MustBeOperational } else { Either }, groups),
-            (Spring::Unknown, Either) => {
-                let assume_damaged = if groups[0] == 1 {
-                        // end of group
-                        variations_2(running, cache, index + 1, MustBeOperational, &mut groups[1..])
-                    } else {
-                        // group still ongoing
-                        groups[0] -= 1;
-                        let v = variations_2(running, cache, index + 1, MustBeDamaged, groups);
-                        groups[0] += 1;
-                        v
-                    };
-                let assume_operational = variations_2(running, cache, index + 1, if groups.is_empty() { MustBeOperational } else { Either }, groups);
-                assume_damaged + assume_operational
+    // ways[(index, group, run)] counts the arrangements of springs[index..] once `group` groups
+    // are complete and the current run of damaged springs is `run` long. filled backwards
+    // from the end of the row into one flat table
+    struct Arrangements<'a> {
+        springs: &'a [Spring],
+        groups: &'a [i32],
+        runs: usize,
+        ways: Vec<u64>,
+    }
+
+    impl<'a> Arrangements<'a> {
+        fn new(springs: &'a [Spring], groups: &'a [i32]) -> Self {
+            let runs = *groups.iter().max().unwrap_or(&0) as usize + 1;
+            let mut arrangements = Arrangements {
+                springs,
+                groups,
+                runs,
+                ways: vec![0; (springs.len() + 1) * (groups.len() + 1) * runs],
+            };
+
+            let complete = groups.len();
+            *arrangements.ways_mut(springs.len(), complete, 0) = 1;
+            if let Some(last) = groups.last() {
+                *arrangements.ways_mut(springs.len(), complete - 1, *last as usize) = 1;
             }
-        };
 
-        cache.insert((index, next, g_clone), v);
-        v
+            for index in (0..springs.len()).rev() {
+                for group in 0..=complete {
+                    for run in 0..runs {
+                        let ways = springs[index].options().iter()
+                            .filter_map(|spring| arrangements.place(*spring, group, run))
+                            .map(|(group, run)| arrangements.at(index + 1, group, run))
+                            .sum();
+                        *arrangements.ways_mut(index, group, run) = ways;
+                    }
+                }
+            }
+
+            arrangements
+        }
+
+        fn offset(&self, index: usize, group: usize, run: usize) -> usize {
+            (index * (self.groups.len() + 1) + group) * self.runs + run
+        }
+
+        fn at(&self, index: usize, group: usize, run: usize) -> u64 {
+            self.ways[self.offset(index, group, run)]
+        }
+
+        fn ways_mut(&mut self, index: usize, group: usize, run: usize) -> &mut u64 {
+            let offset = self.offset(index, group, run);
+            &mut self.ways[offset]
+        }
+
+        // the (group, run) after a known spring, or None if it can't go there
+        fn place(&self, spring: Spring, group: usize, run: usize) -> Option<(usize, usize)> {
+            let target = self.groups.get(group).map(|g| *g as usize);
+            match spring {
+                Spring::Damaged if target.is_some_and(|target| run < target) => Some((group, run + 1)),
+                Spring::Operational if run == 0 => Some((group, 0)),
+                // end of group
+                Spring::Operational if target == Some(run) => Some((group + 1, 0)),
+                _ => None,
+            }
+        }
+
+        fn count(&self) -> u64 {
+            self.at(0, 0, 0)
+        }
+
+        // each way to place the spring at `index`, with the number of arrangements that follow
+        fn choices(&self, index: usize, group: usize, run: usize) -> impl Iterator<Item=(Spring, usize, usize, u64)> + '_ {
+            self.springs[index].options().iter()
+                .filter_map(move |spring| {
+                    let (group, run) = self.place(*spring, group, run)?;
+                    Some((*spring, group, run, self.at(index + 1, group, run)))
+                })
+                .filter(|choice| choice.3 > 0)
+        }
+
+        // every arrangement, so only for small rows
+        fn list(&self) -> (Vec<String>, MemoStats) {
+            let mut memo = Memo::with_stats();
+            let found = self.list_from(&mut memo, 0, 0, 0);
+            (found, memo.stats().unwrap())
+        }
+
+        // the ways to finish the row from a state, shared between every prefix that reaches it
+        fn list_from(&self, memo: &mut Memo<(usize, usize, usize), Vec<String>>, index: usize, group: usize, run: usize) -> Vec<String> {
+            if index == self.springs.len() {
+                return if self.at(index, group, run) > 0 { vec![String::new()] } else { vec![] };
+            }
+            memo.get_or_insert_with((index, group, run), |memo| {
+                self.choices(index, group, run)
+                    .flat_map(|(spring, group, run, _)| {
+                        let first = show(&[spring]);
+                        self.list_from(memo, index + 1, group, run).into_iter()
+                            .map(move |rest| first.clone() + &rest)
+                    })
+                    .collect()
+            })
+        }
+
+        // picks each spring in proportion to the arrangements that follow it, so every
+        // arrangement is equally likely
+        fn sample(&self, rng: &mut Rng) -> Option<String> {
+            if self.count() == 0 {
+                return None;
+            }
+            let (mut group, mut run) = (0, 0);
+            let mut row = vec![];
+            for index in 0..self.springs.len() {
+                let total: u64 = self.choices(index, group, run).map(|choice| choice.3).sum();
+                let mut pick = rng.below(total);
+                let chosen = self.choices(index, group, run)
+                    .find(|choice| {
+                        if pick < choice.3 {
+                            return true;
+                        }
+                        pick -= choice.3;
+                        false
+                    })
+                    .unwrap();
+                row.push(chosen.0);
+                (_, group, run, _) = chosen;
+            }
+            Some(show(&row))
+        }
     }
 
     // fn variations(running: &[Spring], index: usize, in_group: bool, groups: &[i32]) -> u64 {
@@ -1223,139 +2904,188 @@ mod day_12 {
     //     }
     // }
 
+    impl Input {
+        // rows are independent, so they're split evenly across threads
+        fn total(&self, factor: usize) -> u64 {
+            let threads = std::thread::available_parallelism().map_or(1, |n| n.get());
+            let chunk_size = self.input.len().div_ceil(threads).max(1);
+
+            std::thread::scope(|scope| {
+                let handles: Vec<_> = self.input.chunks(chunk_size)
+                    .map(|rows| scope.spawn(move || {
+                        rows.iter()
+                            .map(|(s, g)| {
+                                let (springs, groups) = unfold(s, g, factor);
+                                Arrangements::new(&springs, &groups).count()
+                            })
+                            .sum::<u64>()
+                    }))
+                    .collect();
+
+                handles.into_iter().map(|handle| handle.join().unwrap()).sum()
+            })
+        }
+    }
+
     impl Solution for Input {
         fn part_1(&self) -> Box<dyn Display> {
-            let answer: u64 = self.input.iter()
-                .map(|(s, g)| {
-                    let mut s = s.clone();
-                    let mut cache = HashMap::new();
-                    variations_2(&mut s, &mut cache, 0, Either, &mut g.clone())
-                }).sum();
+            let answer = self.total(1);
 
             Box::new(answer)
         }
 
         fn part_2(&self) -> Box<dyn Display> {
-            let answer: u64 = self.input.iter()
-                .map(|(s, g)| {
-                    let mut unfolded_springs = s.clone();
-                    unfolded_springs.push(Unknown);
-                    unfolded_springs.extend_from_slice(&s);
-                    unfolded_springs.push(Unknown);
-                    unfolded_springs.extend_from_slice(&s);
-                    unfolded_springs.push(Unknown);
-                    unfolded_springs.extend_from_slice(&s);
-                    unfolded_springs.push(Unknown);
-                    unfolded_springs.extend_from_slice(&s);
-
-                    let mut unfolded_groups = g.repeat(5);
-
-                    let mut cache = HashMap::new();
-
-                    variations_2(&mut unfolded_springs, &mut cache, 0, Either, &mut unfolded_groups)
-                }).sum();
+            let answer = self.total(5);
 
             Box::new(answer)
         }
+
+        // every arrangement of rows with a few, and a random one of the rest
+        fn report(&self) -> Option<String> {
+            let mut rng = Rng::new(2023);
+            let mut report = String::new();
+            for (springs, groups) in &self.input {
+                let arrangements = Arrangements::new(springs, groups);
+                let count = arrangements.count();
+                writeln!(report, "{} {:?}: {} arrangements", show(springs), groups, count).unwrap();
+                if count <= 4 {
+                    let (list, stats) = arrangements.list();
+                    for arrangement in list {
+                        writeln!(report, "    {}", arrangement).unwrap();
+                    }
+                    writeln!(report, "    (listing memo: {})", stats).unwrap();
+                } else if let Some(arrangement) = arrangements.sample(&mut rng) {
+                    writeln!(report, "    {} (one at random)", arrangement).unwrap();
+                }
+            }
+            Some(report)
+        }
     }
 }
 
 mod day_13 {
-    use std::collections::HashSet;
-    use std::fmt::Display;
+    use std::fmt::{Display, Formatter, Write};
     use crate::day::{Day, Solution};
 
     pub struct Day13;
 
+    // bit x of rows[y] and bit y of columns[x] are set for a rock at (x, y)
     struct Pattern {
-        pattern: HashSet<(i32, i32)>,
-        x_max: i32,
-        y_max: i32,
+        rows: Vec<u64>,
+        columns: Vec<u64>,
     }
     struct Input {
         input: Vec<Pattern>,
     }
 
-    impl Pattern {
-        fn vertical_reflection(&self, smudges: i32) -> Option<i32> {
-            for x in 0..self.x_max {
-                let mut l = x;
-                let mut r = l + 1;
-                let mirror_size = l.min(self.x_max - l - 1) + 1;
-                let mut difference = 0;
-                for _ in 0..mirror_size {
-                    let left_differences = self.pattern.iter()
-                        .filter(|(x, _)| *x == l)
-                        .filter(|(_, y)| !self.pattern.contains(&(r, *y)))
-                        .count() as i32;
-                    let right_differences = self.pattern.iter()
-                        .filter(|(x, _)| *x == r)
-                        .filter(|(_, y)| !self.pattern.contains(&(l, *y)))
-                        .count() as i32;
-
-                    difference += left_differences + right_differences;
-
-                    l -= 1;
-                    r += 1;
-                }
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    enum Axis {
+        Horizontal,
+        Vertical,
+    }
 
-                if difference == smudges {
-                    return Some(x + 1);
-                }
+    // flipping either cell makes the reflection exact
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    struct Smudge {
+        cell: (usize, usize),
+        mirror: (usize, usize),
+    }
 
+    // a mirror just before row or column `position`. `difference` is how many cells
+    // disagree with their mirror image
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    struct Reflection {
+        axis: Axis,
+        position: usize,
+        difference: u32,
+        smudge: Option<Smudge>,
+    }
+
+    impl Reflection {
+        fn score(&self) -> usize {
+            match self.axis {
+                Axis::Horizontal => 100 * self.position,
+                Axis::Vertical => self.position,
             }
-
-            None
         }
+    }
 
-        fn horizontal_reflection(&self, smudges: i32) -> Option<i32> {
-            for y in 0..self.y_max {
-                let mut t = y;
-                let mut b = t + 1;
-                let mirror_size = t.min(self.y_max - t - 1) + 1;
-                let mut difference = 0;
-                for _ in 0..mirror_size {
-                    let top_differences = self.pattern.iter()
-                        .filter(|(_, y)| *y == t)
-                        .filter(|(x, _)| !self.pattern.contains(&(*x, b)))
-                        .count() as i32;
-                    let bottom_differences = self.pattern.iter()
-                        .filter(|(_, y)| *y == b)
-                        .filter(|(x, _)| !self.pattern.contains(&(*x, t)))
-                        .count() as i32;
-
-                    difference += top_differences + bottom_differences;
-
-                    t -= 1;
-                    b += 1;
-                }
-
-                if difference == smudges {
-                    return Some(y + 1);
-                }
+    impl Display for Reflection {
+        fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+            let lines = match self.axis {
+                Axis::Horizontal => "rows",
+                Axis::Vertical => "columns",
+            };
+            write!(f, "between {} {} and {}, {} different", lines, self.position - 1, self.position, self.difference)?;
+            if let Some(Smudge { cell, mirror }) = self.smudge {
+                write!(f, ", smudge at {:?} or {:?}", cell, mirror)?;
             }
+            Ok(())
+        }
+    }
 
-            None
+    // (position, difference, line and bit of the first mismatch) for every mirror between lines
+    fn mirrors(lines: &[u64]) -> impl Iterator<Item=(usize, u32, Option<(usize, usize, usize)>)> + '_ {
+        (1..lines.len()).map(move |position| {
+            let mut pairs = (0..position.min(lines.len() - position))
+                .map(|i| (position - 1 - i, position + i));
+            let difference = pairs.clone()
+                .map(|(a, b)| (lines[a] ^ lines[b]).count_ones())
+                .sum();
+            let mismatch = pairs
+                .find(|(a, b)| lines[*a] != lines[*b])
+                .map(|(a, b)| (a, b, (lines[a] ^ lines[b]).trailing_zeros() as usize));
+            (position, difference, mismatch)
+        })
+    }
+
+    impl Pattern {
+        // every possible mirror, rows first
+        fn reflections(&self) -> Vec<Reflection> {
+            let horizontal = mirrors(&self.rows).map(|(position, difference, mismatch)| Reflection {
+                axis: Axis::Horizontal,
+                position,
+                difference,
+                smudge: mismatch.filter(|_| difference == 1)
+                    .map(|(a, b, x)| Smudge { cell: (x, a), mirror: (x, b) }),
+            });
+            let vertical = mirrors(&self.columns).map(|(position, difference, mismatch)| Reflection {
+                axis: Axis::Vertical,
+                position,
+                difference,
+                smudge: mismatch.filter(|_| difference == 1)
+                    .map(|(a, b, y)| Smudge { cell: (a, y), mirror: (b, y) }),
+            });
+            horizontal.chain(vertical).collect()
+        }
+
+        // the first mirror on each axis with exactly `smudges` differences
+        fn score(&self, smudges: u32) -> usize {
+            let reflections = self.reflections();
+            [Axis::Horizontal, Axis::Vertical].iter()
+                .filter_map(|axis| reflections.iter().find(|r| r.axis == *axis && r.difference == smudges))
+                .map(|r| r.score())
+                .sum()
         }
     }
 
     impl Day for Day13 {
         fn process_input(&self, input: &str) -> Box<dyn Solution> {
             let input = input.split("\n\n").map(|pattern| {
-                let pattern: HashSet<(i32, i32)> = pattern.lines()
-                    .enumerate()
-                    .flat_map(|(y, line)|
-                        line.chars()
-                            .enumerate()
-                            .filter(|(_, c)| *c == '#')
-                            .map(move |(x, _)| (x as i32, y as i32))
-                    ).collect();
-                let x_max = pattern.iter().map(|p| p.0).max().unwrap();
-                let y_max = pattern.iter().map(|p| p.1).max().unwrap();
+                let grid: Vec<&[u8]> = pattern.lines().map(|line| line.as_bytes()).collect();
+                let width = grid[0].len();
+                assert!(width <= 64 && grid.len() <= 64, "pattern too large for bitmasks");
+
+                let rows = grid.iter()
+                    .map(|line| (0..width).filter(|x| line[*x] == b'#').fold(0, |mask, x| mask | 1 << x))
+                    .collect();
+                let columns = (0..width)
+                    .map(|x| (0..grid.len()).filter(|y| grid[*y][x] == b'#').fold(0, |mask, y| mask | 1 << y))
+                    .collect();
+
                 Pattern {
-                    pattern,
-                    x_max,
-                    y_max,
+                    rows,
+                    columns,
                 }
             }).collect();
 
@@ -1365,22 +3095,41 @@ mod day_13 {
         }
     }
 
+    impl Input {
+        // the mirror each pattern has once its smudge is cleaned, with where the smudge was
+        fn fixes(&self) -> Vec<Option<Reflection>> {
+            self.input.iter()
+                .map(|p| p.reflections().into_iter().find(|r| r.smudge.is_some()))
+                .collect()
+        }
+    }
+
     impl Solution for Input {
         fn part_1(&self) -> Box<dyn Display> {
-            let answer: i32 = self.input.iter().map(|p| {
-                100 * p.horizontal_reflection(0).unwrap_or(0) + p.vertical_reflection(0).unwrap_or(0)
-            }).sum();
+            let answer: usize = self.input.iter().map(|p| p.score(0)).sum();
 
             Box::new(answer)
         }
 
         fn part_2(&self) -> Box<dyn Display> {
-            let answer: i32 = self.input.iter().map(|p| {
-                100 * p.horizontal_reflection(1).unwrap_or(0) + p.vertical_reflection(1).unwrap_or(0)
-            }).sum();
+            let answer: usize = self.input.iter().map(|p| p.score(1)).sum();
 
             Box::new(answer)
         }
+
+        fn report(&self) -> Option<String> {
+            let mut report = String::new();
+            for (i, (pattern, fix)) in self.input.iter().zip(self.fixes()).enumerate() {
+                for reflection in pattern.reflections().iter().filter(|r| r.difference == 0) {
+                    writeln!(report, "pattern {}: reflects {}", i + 1, reflection).unwrap();
+                }
+                match fix {
+                    Some(fix) => writeln!(report, "pattern {}: cleaned, reflects {}", i + 1, fix).unwrap(),
+                    None => writeln!(report, "pattern {}: no single smudge gives a new reflection", i + 1).unwrap(),
+                }
+            }
+            Some(report)
+        }
     }
 }
 
@@ -1749,9 +3498,9 @@ mod day_18 {
     use std::collections::{BTreeSet, HashSet};
     use std::fmt::Display;
     use std::str::FromStr;
-    use regex::Regex;
     use crate::day::{Day, Solution};
     use crate::day_18::Dir::{D, L, R, U};
+    use crate::parse::parse;
 
     pub struct Day18;
 
@@ -1818,28 +3567,22 @@ mod day_18 {
 
     impl Day for Day18 {
         fn process_input(&self, input: &str) -> Box<dyn Solution> {
-            let hex_regex = Regex::new("^\\(#(.{5})(.)\\)$").unwrap();
-            let (plain, hex) = input.lines()
-                .map(|line| {
-                    let mut split = line.split(" ");
-                    let plain = Instruction {
-                        dir: split.next().unwrap().parse().unwrap(),
-                        distance: split.next().unwrap().parse().unwrap(),
-                    };
-                    let hex_captures = hex_regex.captures(split.next().unwrap()).unwrap();
-                    let hex = Instruction {
-                        dir: match hex_captures.get(2).unwrap().as_str() {
-                            "0" => R,
-                            "1" => D,
-                            "2" => L,
-                            "3" => U,
-                            _ => panic!()
-                        },
-                        distance: i32::from_str_radix(hex_captures.get(1).unwrap().as_str(), 16).unwrap(),
-                    };
-                    (plain, hex)
-                })
-                .unzip();
+            let (plain, hex) = parse(input, |p| p.lines(|p| {
+                let plain_dir = p.token(|p| p.fixed(1))?;
+                p.tag(" ")?;
+                let plain = Instruction {
+                    dir: plain_dir,
+                    distance: p.integer()?,
+                };
+                p.tag(" (#")?;
+                let hex_distance = p.radix_integer(5, 16)?;
+                let hex = Instruction {
+                    dir: p.one_of(&[("0", R), ("1", D), ("2", L), ("3", U)])?,
+                    distance: hex_distance,
+                };
+                p.tag(")")?;
+                Ok((plain, hex))
+            })).into_iter().unzip();
 
             Box::new(Input {
                 plain_instructions: plain,
@@ -2006,16 +3749,15 @@ mod day_18 {
 }
 
 mod day_19 {
-    use std::cell::OnceCell;
     use std::cmp::Ordering;
     use std::collections::HashMap;
     use std::fmt::{Debug, Display, Formatter, Write};
     use std::str::FromStr;
-    use regex::Regex;
     use crate::day::{Day, Solution};
     use crate::day_19::FinalOutcome::Accept;
     use crate::day_19::Rule::{AlwaysOutcome, ConditionalOutcome};
     use crate::day_19::RuleOutcome::{OtherWorkflow, Finish};
+    use crate::parse::{parse, ParseResult, Parser};
 
     pub struct Day19;
 
@@ -2084,30 +3826,22 @@ mod day_19 {
         },
     }
 
-    const RULE_REGEX: OnceCell<Regex> = OnceCell::new();
-
-    impl FromStr for Rule {
-        type Err = ();
+    const CATEGORIES: [(&str, usize); 4] = [("x", 0), ("m", 1), ("a", 2), ("s", 3)];
 
-        fn from_str(s: &str) -> Result<Self, Self::Err> {
-            let regex_cell = RULE_REGEX;
-            let regex = regex_cell.get_or_init(|| Regex::new("^([xmas])([<>])([0-9]+):(A|R|[a-z]+)$").unwrap());
-            Ok(match regex.captures(s) {
-                None => AlwaysOutcome(s.parse().unwrap()),
-                Some(captures) => ConditionalOutcome {
-                    index: match captures.get(1).unwrap().as_str() {
-                        "x" => 0,
-                        "m" => 1,
-                        "a" => 2,
-                        "s" => 3,
-                        _   => panic!(),
-                    },
-                    comparison: if captures.get(2).unwrap().as_str() == "<" { Ordering::Less } else { Ordering::Greater },
-                    threshold: captures.get(3).unwrap().as_str().parse().unwrap(),
-                    outcome: captures.get(4).unwrap().as_str().parse().unwrap(),
-                }
-            })
+    fn rule(p: &mut Parser) -> ParseResult<Rule> {
+        if !matches!(p.rest().chars().nth(1), Some('<' | '>')) {
+            return Ok(AlwaysOutcome(p.token(Parser::word)?));
         }
+        let index = p.one_of(&CATEGORIES)?;
+        let comparison = p.one_of(&[("<", Ordering::Less), (">", Ordering::Greater)])?;
+        let threshold = p.integer()?;
+        p.tag(":")?;
+        Ok(ConditionalOutcome {
+            index,
+            comparison,
+            threshold,
+            outcome: p.token(Parser::word)?,
+        })
     }
 
     struct Input {
@@ -2117,28 +3851,30 @@ mod day_19 {
 
     impl Day for Day19 {
         fn process_input(&self, input: &str) -> Box<dyn Solution> {
-            let (workflows_input, parts_input) = input.split_once("\n\n").unwrap();
-
-            let name_and_rules_regex = Regex::new("^([^{]*)\\{([^}]*)}$").unwrap();
-            let workflows = workflows_input.lines()
-                .map(|line| {
-                    let name_and_rules_captures = name_and_rules_regex.captures(line).unwrap();
-                    let id = name_and_rules_captures.get(1).unwrap().as_str().parse().unwrap();
-                    let rules_strings = name_and_rules_captures.get(2).unwrap().as_str().split(",");
-                    (id, rules_strings.map(|s| s.parse().unwrap()).collect())
-                }).collect();
+            let (workflows, parts) = parse(input, |p| {
+                let workflows = p.section(|p| p.lines(|p| {
+                    let id = p.token(Parser::word)?;
+                    p.tag("{")?;
+                    let rules = p.separated(",", rule)?;
+                    p.tag("}")?;
+                    Ok((id, rules))
+                }))?;
+
+                let parts = p.section(|p| p.lines(|p| {
+                    p.tag("{")?;
+                    let mut ratings = [0; 4];
+                    for (key, index) in CATEGORIES {
+                        if index > 0 {
+                            p.tag(",")?;
+                        }
+                        ratings[index] = p.field(key, "=", Parser::integer)?;
+                    }
+                    p.tag("}")?;
+                    Ok(ratings)
+                }))?;
 
-            let ratings_regex = Regex::new("^\\{x=([0-9]*),m=([0-9]*),a=([0-9]*),s=([0-9]*)}$").unwrap();
-            let parts = parts_input.lines()
-                .map(|line| {
-                    let ratings_captures = ratings_regex.captures(line).unwrap();
-                    [
-                        ratings_captures.get(1).unwrap().as_str().parse().unwrap(),
-                        ratings_captures.get(2).unwrap().as_str().parse().unwrap(),
-                        ratings_captures.get(3).unwrap().as_str().parse().unwrap(),
-                        ratings_captures.get(4).unwrap().as_str().parse().unwrap(),
-                    ]
-                }).collect();
+                Ok((workflows.into_iter().collect(), parts))
+            });
 
             Box::new(Input {
                 workflows,